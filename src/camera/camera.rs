@@ -7,16 +7,30 @@ use location::*;
 use std::sync::{Arc,Mutex};
 use std::ops::DerefMut;
 
-use cgmath::{Vector2,Vector3,PerspectiveFov,Basis3,Rotation3};
+use cgmath::{Vector2,Vector3,Basis3,Rotation3};
 use cgmath::{vec2,vec3,Rad};
+use cgmath::EuclideanSpace;
 use glutin::ElementState;
 use glutin::MouseScrollDelta;
 
+use types::Time;
+use consts::{MAP_SIZE,AGENT_SIZE};
+
 use controller::Input;
 
-use super::Viewport;
+use super::{Viewport, Projection};
+use super::viewport::FOVY;
+use super::picking;
 use super::Error;
 
+///Fraction of the remaining way to the target, that the camera passes in a second, the transition is exponential
+const SMOOTHNESS:f32 = 10.0;
+///Longer frames are not integrated at once, so the camera does not jump after a hang
+const MAX_UPDATE_S:f32 = 0.1;
+const MIN_DISTANCE:f32 = 1.0;
+///Pan per tick with a key, in units of the camera distance
+const KEY_PAN_SPEED:f32 = 0.02;
+
 #[derive(Clone)]
 pub struct Camera{
     inner:Arc<Mutex<InnerCamera>>
@@ -29,6 +43,14 @@ struct InnerCamera {
     camera_matrix:Matrix4,
     camera_position:Pos3D,
 
+    ///Panning, following and fitting move these, the camera approaches them smoothly in update
+    target_center_position:Pos3D,
+    target_distance:f32,
+    follow_cursor:bool,
+    last_update:Time,
+
+    ///Kept for the whole session, resizing the window does not reset it
+    projection:Projection,
     viewport:Option<Viewport>,
 }
 
@@ -43,6 +65,12 @@ impl InnerCamera{
             camera_matrix:Matrix4::identity(),
             camera_position:Pos3D::new(0.0,0.0,0.0),
 
+            target_center_position: Pos3D::new(8.0,0.0,8.0),
+            target_distance: 10.0,
+            follow_cursor:false,
+            last_update:Time::now(),
+
+            projection:Projection::Perspective,
             viewport:Viewport::configure(window_width, window_height),
         };
 
@@ -83,28 +111,124 @@ impl InnerCamera{
             _ => 0.0,
         };
 
-        let old_distance=self.distance;
-        let new_distance=if self.distance-scroll_y < 1.0 {
-            1.0
+        let new_distance=if self.target_distance-scroll_y < MIN_DISTANCE {
+            MIN_DISTANCE
         }else{
-            self.distance-scroll_y
+            self.target_distance-scroll_y
+        };
+
+        self.target_distance=new_distance;
+    }
+
+    ///Direction from the camera to the center and to the right of it, both on the ground
+    fn ground_axes(&self) -> (Vector3<f32>, Vector3<f32>) {
+        let (sin,cos)=self.angle.y.sin_cos();
+
+        (vec3(-sin, 0.0, -cos), vec3(cos, 0.0, -sin))
+    }
+
+    ///Moves the center by offsets along the view on the ground, in units of the camera distance
+    fn pan(&mut self, right:f32, forward:f32) {
+        let (forward_axis, right_axis)=self.ground_axes();
+        let center=self.target_center_position + (right_axis*right + forward_axis*forward)*self.target_distance;
+
+        self.set_target_center(center);
+    }
+
+    ///Dragging with the middle button moves the map with the mouse
+    fn pan_by_mouse(&mut self, input:&Input) {
+        let (move_x, move_y)=match self.viewport {
+            Some( ref viewport ) => (
+                input.mouse_move_x as f32 / viewport.height as f32,
+                input.mouse_move_y as f32 / viewport.height as f32
+            ),
+            None => return,
         };
 
-        self.distance=new_distance;
+        self.pan(-move_x, move_y);
+    }
+
+    fn set_target_center(&mut self, center:Pos3D) {
+        let size=MAP_SIZE as f32;
 
-        /*
-        if old_distance.ceil()!=new_distance.ceil() {
-            storage.grid.rebuild(new_distance, &window);
+        self.target_center_position=Pos3D::new(center.x.max(0.0).min(size), 0.0, center.z.max(0.0).min(size));
+    }
+
+    ///Looks at the agent on the cursor tile
+    fn follow(&mut self, x:u32, z:u32) {
+        if self.follow_cursor {
+            let half=AGENT_SIZE as f32/2.0;
+            self.set_target_center(Pos3D::new(x as f32 + half, 0.0, z as f32 + half));
         }
-        */
+    }
+
+    ///Centers the map and moves the camera back, until the sphere around the map fits the narrower field of view
+    fn fit_to_map(&mut self) {
+        let aspect_ratio=match self.viewport {
+            Some( ref viewport ) => viewport.aspect_ratio,
+            None => return,
+        };
+
+        let half=MAP_SIZE as f32/2.0;
+        let radius=half*std::f32::consts::SQRT_2;
+
+        let distance=match self.projection {
+            Projection::Perspective => {
+                let fovx=2.0*((FOVY/2.0).tan()*aspect_ratio).atan();
+                let fov=FOVY.min(fovx);
+
+                radius/(fov/2.0).sin()
+            },
+            //The extents grow with the distance, the narrower one must cover the circle around the map
+            Projection::Orthographic => radius/(FOVY/2.0).tan()/aspect_ratio.min(1.0),
+        };
+
+        self.target_center_position=Pos3D::new(half, 0.0, half);
+        self.target_distance=distance.max(MIN_DISTANCE);
+    }
+
+    fn update(&mut self) {
+        let now=Time::now();
+
+        let dt=match now.duration_since(self.last_update) {
+            Ok(interval) => interval.as_secs() as f32 + interval.subsec_nanos() as f32/1_000_000_000.0,
+            Err(_) => 0.0,
+        };
+
+        self.last_update=now;
+
+        let k=1.0-(-dt.min(MAX_UPDATE_S)*SMOOTHNESS).exp();
+        let center_offset=self.target_center_position-self.center_position;
+
+        self.center_position=self.center_position + center_offset*k;
+        self.distance+=(self.target_distance-self.distance)*k;
 
         self.calc_matrix();
     }
 
+    fn toggle_projection(&mut self) -> Projection {
+        self.projection=match self.projection {
+            Projection::Perspective => Projection::Orthographic,
+            Projection::Orthographic => Projection::Perspective,
+        };
+
+        self.calc_matrix();
+
+        self.projection
+    }
+
     fn calc_matrix(&mut self) {
-        use cgmath::ApproxEq;
         use cgmath::Rotation;
-        use cgmath::EuclideanSpace;
+
+        if self.projection==Projection::Orthographic {
+            //Straight down, the top of the screen keeps pointing in the direction of the yaw
+            let (forward, _)=self.ground_axes();
+
+            self.camera_position=self.center_position + vec3(0.0, self.distance, 0.0);
+            self.camera_matrix=Matrix4::look_at(self.camera_position, self.center_position, forward);
+
+            return;
+        }
 
         let rot_x:Basis3<f32>=Rotation3::from_angle_x(Rad(self.angle.x));
         let rot_y:Basis3<f32>=Rotation3::from_angle_y(Rad(self.angle.y));
@@ -144,20 +268,96 @@ impl Camera{
         ok!()
     }
 
+    pub fn pan_by_mouse(&self, input:&Input) -> Result<(),Error> {
+        mutex_lock!(self.inner => camera);
+        camera.pan_by_mouse(input);
+
+        ok!()
+    }
+
+    ///Pans with keys, directions are -1, 0 or 1
+    pub fn pan_by_keys(&self, right:f32, forward:f32) -> Result<(),Error> {
+        mutex_lock!(self.inner => camera);
+        camera.pan(right*KEY_PAN_SPEED, forward*KEY_PAN_SPEED);
+
+        ok!()
+    }
+
+    pub fn toggle_follow_cursor(&self) -> Result<bool,Error> {
+        mutex_lock!(self.inner => camera);
+        camera.follow_cursor=!camera.follow_cursor;
+
+        ok!(camera.follow_cursor)
+    }
+
+    pub fn follow(&self, x:u32, z:u32) -> Result<(),Error> {
+        mutex_lock!(self.inner => camera);
+        camera.follow(x,z);
+
+        ok!()
+    }
+
+    pub fn fit_to_map(&self) -> Result<(),Error> {
+        mutex_lock!(self.inner => camera);
+        camera.fit_to_map();
+
+        ok!()
+    }
+
+    ///Switches between the perspective and the orthographic top-down view, returns the new one
+    pub fn toggle_projection(&self) -> Result<Projection,Error> {
+        mutex_lock!(self.inner => camera);
+
+        ok!(camera.toggle_projection())
+    }
+
+    ///Moves the camera towards the targets of panning and zooming, must be called every tick
+    pub fn update(&self) -> Result<(),Error> {
+        mutex_lock!(self.inner => camera);
+        camera.update();
+
+        ok!()
+    }
+
     pub fn get_render_camera(&self) -> Result<Option<render::Camera>,Error> {
         mutex_lock!(self.inner => camera);
 
-        let perspective_matrix=match camera.viewport {
-            Some( ref viewport ) => viewport.perspective_matrix.clone(),
+        let projection_matrix=match camera.viewport {
+            Some( ref viewport ) => viewport.projection_matrix(camera.projection, camera.distance),
             None => return ok!(None),
         };
 
         let render_camera=render::Camera::new(
             camera.camera_matrix.clone(),
             camera.camera_position.clone(),
-            perspective_matrix
+            projection_matrix
         );
 
         ok!( Some(render_camera) )
     }
+
+    ///Tile of the ground under the pixel, None if the ray misses the map
+    pub fn pick_tile(&self, x:i32, y:i32) -> Result<Option<(u32,u32)>,Error> {
+        let render_camera=match self.get_render_camera()? {
+            Some(render_camera) => render_camera,
+            None => return ok!(None),
+        };
+
+        let (width,height)={
+            mutex_lock!(self.inner => camera);
+
+            match camera.viewport {
+                Some( ref viewport ) => (viewport.width, viewport.height),
+                None => return ok!(None),
+            }
+        };
+
+        let proj_view_matrix=render_camera.projection_matrix * render_camera.camera_matrix;
+
+        let tile=picking::unproject(&proj_view_matrix, width, height, x as f32, y as f32)
+            .and_then(|(origin, direction)| picking::intersect_ground(origin, direction, 0.0))
+            .and_then(picking::tile_at);
+
+        ok!(tile)
+    }
 }
@@ -3,8 +3,10 @@
 pub mod camera;
 pub use self::camera::Camera;
 
+pub mod picking;
+
 pub mod viewport;
-pub use self::viewport::Viewport;
+pub use self::viewport::{Viewport, Projection};
 
 pub mod error;
 pub use self::error::Error;
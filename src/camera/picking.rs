@@ -0,0 +1,153 @@
+use std;
+use location::*;
+
+use consts::MAP_SIZE;
+
+use cgmath::{Vector3,Vector4,SquareMatrix};
+
+///Rays closer to parallel with the ground never reach the map
+const MIN_RAY_Y:f32 = 0.000_001;
+
+///Ray from the near plane through the pixel, in world space. Pixel coordinates go from the top left corner
+///of the window, the same way as window events report them
+pub fn unproject(proj_view_matrix:&Matrix4, width:u32, height:u32, x:f32, y:f32) -> Option<(Pos3D, Vector3<f32>)> {
+    let inverse=match proj_view_matrix.invert() {
+        Some(inverse) => inverse,
+        None => return None,
+    };
+
+    let ndc_x=x/width as f32*2.0 - 1.0;
+    let ndc_y=1.0 - y/height as f32*2.0;
+
+    let near=from_clip(&inverse, Vector4::new(ndc_x, ndc_y, -1.0, 1.0));
+    let far=from_clip(&inverse, Vector4::new(ndc_x, ndc_y, 1.0, 1.0));
+
+    match (near, far) {
+        (Some(near), Some(far)) => Some((near, far-near)),
+        _ => None,
+    }
+}
+
+///Pixel of the world point, the inverse of the unprojection. None if the point is behind the camera
+pub fn project(proj_view_matrix:&Matrix4, width:u32, height:u32, point:Pos3D) -> Option<(f32,f32)> {
+    let clip=*proj_view_matrix*Vector4::new(point.x, point.y, point.z, 1.0);
+
+    if clip.w<=std::f32::EPSILON {
+        return None;
+    }
+
+    let (ndc_x, ndc_y)=(clip.x/clip.w, clip.y/clip.w);
+
+    Some(((ndc_x+1.0)/2.0*width as f32, (1.0-ndc_y)/2.0*height as f32))
+}
+
+///Point of the ray on the horizontal plane at the given height, None if the plane is behind or along the ray
+pub fn intersect_ground(origin:Pos3D, direction:Vector3<f32>, ground_y:f32) -> Option<Pos3D> {
+    if direction.y.abs()<MIN_RAY_Y {
+        return None;
+    }
+
+    let t=(ground_y-origin.y)/direction.y;
+
+    if t<0.0 {
+        return None;
+    }
+
+    Some(origin + direction*t)
+}
+
+///Tile, that contains the point, None outside of the map
+pub fn tile_at(point:Pos3D) -> Option<(u32,u32)> {
+    let (x,z)=(point.x.floor(), point.z.floor());
+
+    if x<0.0 || z<0.0 || x>=MAP_SIZE as f32 || z>=MAP_SIZE as f32 {
+        return None;
+    }
+
+    Some((x as u32, z as u32))
+}
+
+fn from_clip(inverse:&Matrix4, clip:Vector4<f32>) -> Option<Pos3D> {
+    let world=*inverse*clip;
+
+    if world.w.abs()<std::f32::EPSILON {
+        return None;
+    }
+
+    Some(Pos3D::new(world.x/world.w, world.y/world.w, world.z/world.w))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cgmath::{PerspectiveFov, Rad, vec3};
+
+    const WIDTH:u32 = 1024;
+    const HEIGHT:u32 = 768;
+
+    fn proj_view_matrix(eye:Pos3D, center:Pos3D) -> Matrix4 {
+        let perspective=PerspectiveFov {
+            fovy:Rad(0.5),
+            aspect:WIDTH as f32 / HEIGHT as f32,
+            near:0.1,
+            far:1000.0,
+        };
+
+        Matrix4::from(perspective) * Matrix4::look_at(eye, center, vec3(0.0, 1.0, 0.0))
+    }
+
+    fn assert_close(a:Pos3D, b:Pos3D) {
+        assert!((a.x-b.x).abs()<0.01 && (a.y-b.y).abs()<0.01 && (a.z-b.z).abs()<0.01, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn center_of_screen_hits_look_at_point() {
+        let center=Pos3D::new(8.0, 0.0, 8.0);
+        let matrix=proj_view_matrix(Pos3D::new(2.0, 7.0, 1.0), center);
+
+        let (origin, direction)=unproject(&matrix, WIDTH, HEIGHT, WIDTH as f32/2.0, HEIGHT as f32/2.0).unwrap();
+
+        assert_close(intersect_ground(origin, direction, 0.0).unwrap(), center);
+    }
+
+    #[test]
+    fn unprojection_inverts_projection() {
+        let matrix=proj_view_matrix(Pos3D::new(-3.0, 9.0, 4.0), Pos3D::new(8.0, 0.0, 8.0));
+
+        for &point in [Pos3D::new(0.5, 0.0, 0.5), Pos3D::new(12.3, 0.0, 3.7), Pos3D::new(15.9, 0.0, 15.1)].iter() {
+            let (x,y)=project(&matrix, WIDTH, HEIGHT, point).unwrap();
+            let (origin, direction)=unproject(&matrix, WIDTH, HEIGHT, x, y).unwrap();
+
+            assert_close(intersect_ground(origin, direction, 0.0).unwrap(), point);
+        }
+    }
+
+    #[test]
+    fn top_of_screen_misses_ground_when_looking_at_horizon() {
+        let matrix=proj_view_matrix(Pos3D::new(8.0, 1.0, 0.0), Pos3D::new(8.0, 1.0, 8.0));
+
+        let (origin, direction)=unproject(&matrix, WIDTH, HEIGHT, WIDTH as f32/2.0, 0.0).unwrap();
+
+        assert!(intersect_ground(origin, direction, 0.0).is_none());
+    }
+
+    #[test]
+    fn orthographic_top_down_picks_under_pixel() {
+        let center=Pos3D::new(8.0, 0.0, 8.0);
+        let view=Matrix4::look_at(Pos3D::new(8.0, 10.0, 8.0), center, vec3(0.0, 0.0, -1.0));
+        let matrix=::cgmath::ortho(-8.0, 8.0, -6.0, 6.0, 0.1, 1000.0) * view;
+
+        //Quarter of the width to the right and of the height down is 4 units to +x and 3 units to +z
+        let (origin, direction)=unproject(&matrix, WIDTH, HEIGHT, WIDTH as f32*0.75, HEIGHT as f32*0.75).unwrap();
+
+        assert_close(intersect_ground(origin, direction, 0.0).unwrap(), Pos3D::new(12.0, 0.0, 11.0));
+    }
+
+    #[test]
+    fn tiles_outside_of_map_are_not_picked() {
+        assert_eq!(tile_at(Pos3D::new(3.5, 0.0, 7.2)), Some((3, 7)));
+        assert_eq!(tile_at(Pos3D::new(-0.1, 0.0, 7.2)), None);
+        assert_eq!(tile_at(Pos3D::new(3.5, 0.0, MAP_SIZE as f32)), None);
+    }
+}
@@ -1,10 +1,23 @@
-
+use cgmath;
 use cgmath::{Rad, PerspectiveFov,Matrix4};
 
+///Vertical field of view in radians
+pub const FOVY:f32 = 0.5;
+
+const NEAR:f32 = 0.1;
+const FAR:f32 = 1000.0;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Projection {
+    Perspective,
+    ///Looks straight down, the extents grow with the camera distance
+    Orthographic,
+}
+
 pub struct Viewport{
     pub width:u32,
     pub height:u32,
-    pub perspective_matrix:Matrix4<f32>,
+    pub aspect_ratio:f32,
 }
 
 impl Viewport{
@@ -13,21 +26,38 @@ impl Viewport{
 
         let aspect_ratio=width as f32 / height as f32;
 
-        let perspective=PerspectiveFov{
-            fovy:Rad(0.5),
-            aspect:aspect_ratio,
-            near:0.1,
-            far:1000.0,
-        };
-
-        let perspective_matrix=Matrix4::from(perspective);
-
         Some(
             Viewport{
                 width:width,
                 height:height,
-                perspective_matrix:perspective_matrix,
+                aspect_ratio:aspect_ratio,
             }
         )
     }
+
+    ///Orthographic extents are chosen so, that the ground around the center has the same size as in the perspective
+    pub fn projection_matrix(&self, projection:Projection, distance:f32) -> Matrix4<f32> {
+        match projection {
+            Projection::Perspective => {
+                let perspective=PerspectiveFov{
+                    fovy:Rad(FOVY),
+                    aspect:self.aspect_ratio,
+                    near:NEAR,
+                    far:FAR,
+                };
+
+                Matrix4::from(perspective)
+            },
+            Projection::Orthographic => {
+                let half_height=Self::ortho_half_height(distance);
+                let half_width=half_height*self.aspect_ratio;
+
+                cgmath::ortho(-half_width, half_width, -half_height, half_height, NEAR, FAR)
+            }
+        }
+    }
+
+    pub fn ortho_half_height(distance:f32) -> f32 {
+        distance*(FOVY/2.0).tan()
+    }
 }
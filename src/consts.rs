@@ -1,5 +1,18 @@
 
 
 pub const MAP_SIZE:usize = 16;
+pub const MAP_FILE_NAME:&str = "map.txt";
+pub const ASSETS_FILE_NAME:&str = "assets.json";
+///Relative to the working directory, like the assets. The source tree of the build is the fallback
+pub const SHADERS_DIRECTORY:&str = "src/render/pipelines/shaders";
+pub const SHADERS_FALLBACK_DIRECTORY:&str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/render/pipelines/shaders");
+pub const AGENT_SIZE:u32 = 2;
 pub const KEY_LIMIT:usize = 150;
-pub const DELAY:u32 = 200;
\ No newline at end of file
+pub const DELAY:u32 = 200;
+pub const DEFAULT_FPS:u32 = 50;
+
+///The agent footprint has to stay on the map, so the agent does not reach the last tiles
+pub fn clamp_agent_position(x:u32, z:u32) -> (u32,u32) {
+    let max=MAP_SIZE as u32 - AGENT_SIZE;
+    (x.min(max), z.min(max))
+}
@@ -9,6 +9,7 @@ use glutin::EventsLoop;
 use glutin::WindowEvent;
 use glutin::ElementState;
 use glutin::VirtualKeyCode;
+use glutin::MouseButton;
 
 use std::thread;
 use std::thread::JoinHandle;
@@ -31,10 +32,16 @@ use super::Error;
 use super::ControllerCommand;
 use super::GUI;
 use super::Cursor;
+use super::Input;
 
 pub type ControllerSender = reactor::Sender<ThreadSource,ControllerCommand>;
 pub type ControllerReceiver = reactor::Receiver<ThreadSource,ControllerCommand>;
 
+///Press and release of the left button closer than this is a click, otherwise it is a rotation of the camera
+const CLICK_DISTANCE:i32 = 4;
+///Factor of the trace thickness per key press
+const TRACE_THICKNESS_STEP:f32 = 1.25;
+
 pub struct Controller {
     controller_receiver:ControllerReceiver,
     supervisor_sender:SupervisorSender,
@@ -44,7 +51,9 @@ pub struct Controller {
     events_loop:EventsLoop,
     gui:GUI,
     camera:Camera,
-    cursor:Cursor
+    cursor:Cursor,
+    ///Mouse position, where the left button has been pressed
+    click_start:Option<(i32,i32)>,
 }
 
 impl Controller{
@@ -167,7 +176,8 @@ impl Controller{
             events_loop,
             gui:GUI::new(),
             camera,
-            cursor
+            cursor,
+            click_start:None,
         };
 
         ok!(controller)
@@ -193,6 +203,7 @@ impl Controller{
             }
 
             self.handle_cursor()?;
+            self.handle_camera()?;
         }
     }
 
@@ -206,6 +217,8 @@ impl Controller{
         let camera=&self.camera;
         let supervisor_sender=&mut self.supervisor_sender;
         let render_sender=&mut self.render_sender;
+        let process_sender=&mut self.process_sender;
+        let click_start=&mut self.click_start;
         let mut result=Ok(());
 
         events_loop.poll_events(move|event| {
@@ -229,11 +242,48 @@ impl Controller{
 
                             if gui.input.left_mouse_button==ElementState::Pressed {
                                 camera.rotate(&gui.input)?;
+                            }else if gui.input.middle_mouse_button==ElementState::Pressed {
+                                camera.pan_by_mouse(&gui.input)?;
+                            }else{
+                                //Hover moves the cursor
+                                let hover=camera.pick_tile(x as i32, y as i32)?;
+                                try_send!(render_sender, RenderCommand::HoverTile(hover));
+
+                                match hover {
+                                    Some((tile_x, tile_z)) => cursor.move_to(tile_x, tile_z)?,
+                                    None => {},
+                                }
                             }
                         },
                         WindowEvent::MouseInput{device_id, state, button} => {
                             gui.on_mouse_button(button, state);
 
+                            let mouse=match (gui.input.mouse_x, gui.input.mouse_y) {
+                                (Some(mouse_x), Some(mouse_y)) => Some((mouse_x, mouse_y)),
+                                _ => None,
+                            };
+
+                            match (button, state) {
+                                (MouseButton::Left, ElementState::Pressed) =>
+                                    *click_start=mouse,
+                                (MouseButton::Left, ElementState::Released) => {
+                                    match (click_start.take(), mouse) {
+                                        (Some(start), Some(end)) if (start.0-end.0).abs()<=CLICK_DISTANCE && (start.1-end.1).abs()<=CLICK_DISTANCE => {
+                                            //Left click sets A, then B
+                                            match camera.pick_tile(end.0, end.1)? {
+                                                Some((tile_x, tile_z)) => {
+                                                    cursor.move_to(tile_x, tile_z)?;
+                                                    cursor.on_enter()?;
+                                                },
+                                                None => {},
+                                            }
+                                        },
+                                        _ => {},
+                                    }
+                                },
+                                _ => {},
+                            }
+
                             if gui.input.right_mouse_button==ElementState::Pressed {
 
                             }
@@ -246,8 +296,62 @@ impl Controller{
                                 Some(key) => {
                                     gui.on_key(key, input.state);
 
-                                    if key==VirtualKeyCode::Return && input.state==ElementState::Released {
-                                        cursor.on_enter()?;
+                                    if input.state==ElementState::Released {
+                                        match key {
+                                            VirtualKeyCode::Return =>
+                                                cursor.on_enter()?,
+                                            VirtualKeyCode::G =>
+                                                try_send!(render_sender, RenderCommand::ToggleGrid),
+                                            VirtualKeyCode::J =>
+                                                try_send!(render_sender, RenderCommand::ToggleHeatmapScope),
+                                            VirtualKeyCode::R =>
+                                                cursor.reset()?,
+                                            VirtualKeyCode::C =>
+                                                try_send!(render_sender, RenderCommand::ToggleClearance),
+                                            VirtualKeyCode::F =>
+                                                try_send!(render_sender, RenderCommand::ToggleFlowField),
+                                            VirtualKeyCode::M =>
+                                                try_send!(render_sender, RenderCommand::NextRenderMode),
+                                            VirtualKeyCode::K =>
+                                                try_send!(render_sender, RenderCommand::ToggleAmbientOcclusion),
+                                            VirtualKeyCode::LBracket =>
+                                                try_send!(render_sender, RenderCommand::ScaleTraceThickness(1.0/TRACE_THICKNESS_STEP)),
+                                            VirtualKeyCode::RBracket =>
+                                                try_send!(render_sender, RenderCommand::ScaleTraceThickness(TRACE_THICKNESS_STEP)),
+                                            VirtualKeyCode::P =>
+                                                try_send!(process_sender, ProcessCommand::NextPathfinder),
+                                            VirtualKeyCode::V =>
+                                                try_send!(process_sender, ProcessCommand::ToggleReferenceRoute),
+                                            VirtualKeyCode::L => {
+                                                let follow=camera.toggle_follow_cursor()?;
+                                                println!("Camera follows cursor: {}", if follow {"on"} else {"off"});
+
+                                                if follow {
+                                                    camera.follow(cursor.x, cursor.z)?;
+                                                }
+                                            },
+                                            VirtualKeyCode::Home =>
+                                                camera.fit_to_map()?,
+                                            VirtualKeyCode::O => {
+                                                let projection=camera.toggle_projection()?;
+                                                println!("Camera projection: {:?}", projection);
+                                            },
+                                            VirtualKeyCode::H =>
+                                                try_send!(render_sender, RenderCommand::ToggleHud),
+                                            VirtualKeyCode::I =>
+                                                try_send!(render_sender, RenderCommand::PrintStats),
+                                            VirtualKeyCode::F11 =>
+                                                try_send!(render_sender, RenderCommand::ToggleCapture),
+                                            VirtualKeyCode::F12 => {
+                                                let seconds=match Time::now().duration_since(std::time::UNIX_EPOCH) {
+                                                    Ok(duration) => duration.as_secs(),
+                                                    Err(_) => 0,
+                                                };
+
+                                                try_send!(render_sender, RenderCommand::Screenshot(format!("screenshot_{}.png", seconds)));
+                                            },
+                                            _ => {}
+                                        }
                                     }
                                 }
                                 _ => {},
@@ -279,8 +383,30 @@ impl Controller{
 
         if moved {
             try_send!(self.render_sender, RenderCommand::MoveCursor(self.cursor.x,self.cursor.z));
+
+            //Hover moves the cursor too, but following it would slide the map under the mouse
+            self.camera.follow(self.cursor.x,self.cursor.z)?;
+        }
+
+        ok!()
+    }
+
+    ///WASD pans the camera, the camera moves smoothly towards its targets every tick
+    fn handle_camera(&mut self) -> Result<(),Error> {
+        let direction=|positive:VirtualKeyCode, negative:VirtualKeyCode, input:&Input| {
+            let pressed=|key| if input.key(key)==ElementState::Pressed {1.0} else {0.0};
+            pressed(positive)-pressed(negative)
+        };
+
+        let right=direction(VirtualKeyCode::D, VirtualKeyCode::A, &self.gui.input);
+        let forward=direction(VirtualKeyCode::W, VirtualKeyCode::S, &self.gui.input);
+
+        if right!=0.0 || forward!=0.0 {
+            self.camera.pan_by_keys(right, forward)?;
         }
 
+        self.camera.update()?;
+
         ok!()
     }
 
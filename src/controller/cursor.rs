@@ -1,6 +1,6 @@
 use nes::{ErrorInfo,ErrorInfoTrait};
 
-use consts::MAP_SIZE;
+use consts::{MAP_SIZE,clamp_agent_position};
 
 use glutin::ElementState;
 
@@ -137,6 +137,20 @@ impl Cursor {
         }
     }
 
+    ///Moves the agent to the tile under the mouse, the agent has to stay on the map
+    pub fn move_to(&mut self, x:u32, z:u32) -> Result<(),Error> {
+        let (x,z)=clamp_agent_position(x,z);
+
+        if (x,z)!=(self.x,self.z) {
+            self.x=x;
+            self.z=z;
+
+            try_send!(self.render_sender, RenderCommand::MoveCursor(self.x,self.z));
+        }
+
+        ok!()
+    }
+
     pub fn on_enter(&mut self) -> Result<(),Error> {
         if self.a.is_none() {
             self.a=Some((self.x,self.z));
@@ -154,6 +168,18 @@ impl Cursor {
         ok!()
     }
 
+    ///Forgets A and B and asks the process to clear the results of the query
+    pub fn reset(&mut self) -> Result<(),Error> {
+        self.a=None;
+        self.b=None;
+
+        try_send!(self.render_sender, RenderCommand::SetCursorA( self.a ));
+        try_send!(self.render_sender, RenderCommand::SetCursorB( self.b ));
+        try_send!(self.process_sender, ProcessCommand::Reset);
+
+        ok!()
+    }
+
     pub fn algorithm_end(&mut self) -> Result<(),Error> {
         //self.a=None;
         self.b=None;
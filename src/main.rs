@@ -13,6 +13,9 @@ extern crate gfx_device_gl as gfx_gl;
 extern crate cgmath;
 extern crate glutin;
 extern crate image;
+extern crate serde_json;
+extern crate xml;
+extern crate rusttype;
 
 pub mod types;
 pub mod consts;
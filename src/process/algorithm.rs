@@ -3,24 +3,28 @@ use nes::{ErrorInfo,ErrorInfoTrait};
 use types::*;
 use consts::*;
 
+use std;
 use std::thread;
+use std::cmp::Ordering;
 
 use render::{RenderSender,RenderCommand};
 
 use super::Error;
 use super::TracePool;
 use super::Map;
+use super::grid;
 
-use ::Storage;
-
-const RED:[f32;4] = [0.7,0.0,0.0,0.7];
-const BLUE:[f32;4] = [0.0,0.0,0.7,0.7];
-const GREEN:[f32;4] = [0.0,0.7,0.0,0.7];
-const AQUA:[f32;4] = [0.0,0.7,0.7,0.7];
-const YELLOW:[f32;4] = [0.7,0.7,0.0,0.7];
+pub const RED:[f32;4] = [0.7,0.0,0.0,0.7];
+pub const BLUE:[f32;4] = [0.0,0.0,0.7,0.7];
+pub const GREEN:[f32;4] = [0.0,0.7,0.0,0.7];
+pub const AQUA:[f32;4] = [0.0,0.7,0.7,0.7];
+pub const YELLOW:[f32;4] = [0.7,0.7,0.0,0.7];
 
 const MAP_SIZE1:u32 = MAP_SIZE as u32 - 1;
 const MAP_SIZE2:u32 = MAP_SIZE as u32 - 2;
+///Nested hooks of one query, deeper traces are given up. Every hook traces two lines, so the search
+///walks at most 2^MAX_HOOK_DEPTH lines
+const MAX_HOOK_DEPTH:u32 = 5;
 
 #[derive(Debug, Copy, Clone)]
 enum Direction {
@@ -32,8 +36,8 @@ enum Direction {
 
 #[derive(Copy,Clone,Eq,PartialEq)]
 pub struct Pos2D {
-    x:u32,
-    z:u32,
+    pub x:u32,
+    pub z:u32,
 }
 
 impl Pos2D {
@@ -50,6 +54,70 @@ pub enum HookMode{
     MostRemote
 }
 
+///Where the hook algorithm shows its search. The viewer draws traces and walked tiles step by step,
+///the benchmark runs it hidden, without the delay and the log
+pub enum HookView<'a> {
+    Drawn(&'a mut TracePool, &'a mut RenderSender),
+    Hidden,
+}
+
+impl<'a> HookView<'a> {
+    fn add_trace(&mut self, a:Pos2D, b:Pos2D) -> Result<TraceID,Error> {
+        match *self {
+            HookView::Drawn(ref mut traces, _) => add_trace(traces, a, b),
+            HookView::Hidden => ok!(TraceID::zeroed()),
+        }
+    }
+
+    fn set_trace_color(&mut self, trace_id:TraceID, color:[f32;4]) -> Result<(),Error> {
+        match *self {
+            HookView::Drawn(_, ref mut render_sender) => {
+                try_send!(render_sender, RenderCommand::SetTraceColor(trace_id,color));
+            },
+            HookView::Hidden => {},
+        }
+
+        ok!()
+    }
+
+    ///Tiles of a new walk are not steps from the last tile of the previous one
+    fn start_walk(&mut self) -> Result<(),Error> {
+        match *self {
+            HookView::Drawn(_, ref mut render_sender) => {
+                try_send!(render_sender, RenderCommand::StartWalk);
+            },
+            HookView::Hidden => {},
+        }
+
+        ok!()
+    }
+
+    fn add_tile(&mut self, x:u32, z:u32, walked:bool) -> Result<(),Error> {
+        match *self {
+            HookView::Drawn(_, ref mut render_sender) => {
+                try_send!(render_sender, RenderCommand::AddTile(x,z,walked));
+            },
+            HookView::Hidden => {},
+        }
+
+        ok!()
+    }
+
+    fn pause(&self) {
+        match *self {
+            HookView::Drawn(..) => thread::sleep_ms(DELAY),
+            HookView::Hidden => {},
+        }
+    }
+
+    fn log(&self, message:std::fmt::Arguments) {
+        match *self {
+            HookView::Drawn(..) => println!("{}", message),
+            HookView::Hidden => {},
+        }
+    }
+}
+
 fn calc_trace(a:Pos2D, b:Pos2D) -> (Direction, f32, f32, Option<(f32,f32)>){
     use std::f32::consts::PI;
 
@@ -119,49 +187,55 @@ fn calc_trace(a:Pos2D, b:Pos2D) -> (Direction, f32, f32, Option<(f32,f32)>){
     (dir, angle, len, k)
 }
 
-pub fn add_trace(traces:&mut TracePool, storage:&Storage, a:Pos2D, b:Pos2D) -> Result<TraceID,Error> {
+pub fn add_trace(traces:&mut TracePool, a:Pos2D, b:Pos2D) -> Result<TraceID,Error> {
+    add_colored_trace(traces, a, b, YELLOW)
+}
+
+pub fn add_colored_trace(traces:&mut TracePool, a:Pos2D, b:Pos2D, color:[f32;4]) -> Result<TraceID,Error> {
     let (_,angle,len,_) = calc_trace(a,b);
 
-    let trace_id=traces.insert(storage, a.x+1, a.z+1, angle, len, YELLOW)?;
+    let trace_id=traces.insert(a.x+1, a.z+1, angle, len, color)?;
 
     ok!(trace_id)
 }
 
-fn find_obstracle(render_sender:&mut RenderSender, map:&mut Map, a:Pos2D, b:Pos2D) -> Result<Option<Pos2D>,Error> {
-    let (dir,angle,len,k) = calc_trace(a,b);
+fn find_obstracle(view:&mut HookView, map:&Map, a:Pos2D, b:Pos2D) -> Result<Option<Pos2D>,Error> {
+    let (dir,_,_,k) = calc_trace(a,b);
     let mut previous = a;
 
-    match dir {
-        Direction::Front => {
-            for z in a.z..(b.z + 1) {
-                let x = match k {
-                    None => a.x as u32,
-                    Some((k, b)) => {
-                        let x = (z as f32 - b) / k;
-                        x as u32
-                    }
-                };
+    view.start_walk()?;
 
-                println!("T {} {}",x,z);
-                let is_obstracle = !map.is_floor(x, z) || !map.is_floor(x + 1, z) || !map.is_floor(x, z + 1) || !map.is_floor(x + 1, z + 1);
+    //k is z(x) for the front and back traces and x(z) for the left and right ones
+    let cross = |main:u32, start:u32| match k {
+        None => start,
+        Some((k, b)) => ((main as f32 - b) / k) as u32,
+    };
 
-                if is_obstracle {
-                    return ok!(Some(previous));
-                }
+    let tiles:Vec<Pos2D> = match dir {
+        Direction::Front => (a.z..(b.z + 1)).map(|z| Pos2D::new(cross(z, a.x), z)).collect(),
+        Direction::Back => (b.z..(a.z + 1)).rev().map(|z| Pos2D::new(cross(z, a.x), z)).collect(),
+        Direction::Right => (a.x..(b.x + 1)).map(|x| Pos2D::new(x, cross(x, a.z))).collect(),
+        Direction::Left => (b.x..(a.x + 1)).rev().map(|x| Pos2D::new(x, cross(x, a.z))).collect(),
+    };
 
-                previous = Pos2D::new(x, z);
-                try_send!(render_sender, RenderCommand::AddTile(x,z,true));
+    for tile in tiles {
+        view.log(format_args!("T {} {}",tile.x,tile.z));
+        let is_obstracle = !map.fits(tile.x, tile.z, AGENT_SIZE);
 
-                thread::sleep_ms(DELAY);
-            }
-        },
-        _ => {},
+        if is_obstracle {
+            return ok!(Some(previous));
+        }
+
+        previous = tile;
+        view.add_tile(tile.x,tile.z,true)?;
+
+        view.pause();
     }
 
     ok!(None)
 }
 
-fn hook(render_sender:&mut RenderSender, map:&Map, obstracle_pos:Pos2D, obstracle_dir:Direction, clockwise:bool, a:Pos2D, b:Pos2D, len:f32, mode:HookMode) -> Result<Option<Pos2D>,Error> {
+fn hook(view:&mut HookView, map:&Map, obstracle_pos:Pos2D, obstracle_dir:Direction, clockwise:bool, a:Pos2D, b:Pos2D, len:f32, mode:HookMode) -> Result<Option<Pos2D>,Error> {
     let (_,_,_,k) = calc_trace(a,b);
 
     let ax=a.x as f32 + 1.0;
@@ -178,10 +252,12 @@ fn hook(render_sender:&mut RenderSender, map:&Map, obstracle_pos:Pos2D, obstracl
 
     let mut p=init_point(map,c,obstracle_dir,clockwise);
 
+    view.start_walk()?;
+
     for i in 0..60 {
-        println!("CUR:{} {} POINT:{} {}",c.x,c.z,p.x,p.z);
-        thread::sleep_ms(DELAY);
-        try_send!(render_sender, RenderCommand::AddTile(c.x,c.z,true));
+        view.log(format_args!("CUR:{} {} POINT:{} {}",c.x,c.z,p.x,p.z));
+        view.pause();
+        view.add_tile(c.x,c.z,true)?;
 
         if move_dir.is_some() {
             let dist_ab=((bz-az)*c.x as f32 - (bx-ax)*(c.z) as f32 + bx*az - bz*ax).abs() / len;
@@ -223,18 +299,18 @@ fn hook(render_sender:&mut RenderSender, map:&Map, obstracle_pos:Pos2D, obstracl
             }
         }
 
-        move_point(map, c, &mut p, move_dir, clockwise);
-        try_send!(render_sender, RenderCommand::AddTile(p.x,p.z,false));
+        move_point(view, map, c, &mut p, move_dir, clockwise);
+        view.add_tile(p.x,p.z,false)?;
 
         if clockwise {
             //4,8,C
             if p.z>=c.z && p.x+1==c.x {
-                println!("48C");
+                view.log(format_args!("48C"));
                 if can_move(map,c,Direction::Front) {
                     c.z+=1;
                     move_dir=Some(Direction::Front);
                 }else {
-                    match is_bridge(map, c, Direction::Front) {
+                    match is_bridge(view, map, c, Direction::Front) {
                         Some(o) => {
                             p = o;
 
@@ -250,12 +326,12 @@ fn hook(render_sender:&mut RenderSender, map:&Map, obstracle_pos:Pos2D, obstracl
                 }
             //D,E,F
             }else if p.x>=c.x && p.z==c.z+2 {
-                println!("DEF");
+                view.log(format_args!("DEF"));
                 if can_move(map,c,Direction::Right) {
                     c.x+=1;
                     move_dir=Some(Direction::Right);
                 }else{
-                    match is_bridge(map,c,Direction::Right) {
+                    match is_bridge(view, map,c,Direction::Right) {
                         Some(o) => {
                             p=o;
 
@@ -271,12 +347,12 @@ fn hook(render_sender:&mut RenderSender, map:&Map, obstracle_pos:Pos2D, obstracl
                 }
             //3,7,B
             }else if p.z<c.z+2 && p.x==c.x+2 {
-                println!("37B");
+                view.log(format_args!("37B"));
                 if can_move(map,c,Direction::Back) {
                     c.z-=1;
                     move_dir=Some(Direction::Back);
                 }else{
-                    match is_bridge(map,c,Direction::Back) {
+                    match is_bridge(view, map,c,Direction::Back) {
                         Some(o) => {
                             p=o;
 
@@ -292,12 +368,12 @@ fn hook(render_sender:&mut RenderSender, map:&Map, obstracle_pos:Pos2D, obstracl
                 }
             //0,1,2
             }else if p.x<c.x+2 && p.z+1==c.z {
-                println!("012");
+                view.log(format_args!("012"));
                 if can_move(map,c,Direction::Left) {
                     c.x-=1;
                     move_dir=Some(Direction::Left);
                 }else{
-                    match is_bridge(map,c,Direction::Left) {
+                    match is_bridge(view, map,c,Direction::Left) {
                         Some(o) => {
                             p=o;
 
@@ -345,7 +421,7 @@ fn init_point(map:&Map, c:Pos2D, obstracle_dir:Direction, clockwise:bool) -> Pos
     }
 }
 
-fn move_point(map:&Map, c:Pos2D, p:&mut Pos2D, move_dir:Option<Direction>, clockwise:bool) {
+fn move_point(view:&HookView, map:&Map, c:Pos2D, p:&mut Pos2D, move_dir:Option<Direction>, clockwise:bool) {
     match move_dir {
         Some(Direction::Front) => {
             if p.z < MAP_SIZE1 && map.is_obstracle(p.x,p.z+1) {
@@ -372,9 +448,9 @@ fn move_point(map:&Map, c:Pos2D, p:&mut Pos2D, move_dir:Option<Direction>, clock
 
     if clockwise {
         for _ in 0..5 {
-            println!("Move point {} {} {} {}",p.x,p.z, c.x, c.z);
+            view.log(format_args!("Move point {} {} {} {}",p.x,p.z, c.x, c.z));
             if p.x + 1 == c.x && p.z < c.z + 2 {
-                println!("A");
+                view.log(format_args!("A"));
                 if p.z < MAP_SIZE1 && map.is_obstracle(p.x, p.z + 1) {
                     p.z += 1;
                 } else {
@@ -383,7 +459,7 @@ fn move_point(map:&Map, c:Pos2D, p:&mut Pos2D, move_dir:Option<Direction>, clock
             }
 
             if p.z == c.z + 2 && p.x < c.x + 2 {
-                println!("B");
+                view.log(format_args!("B"));
                 if p.x < MAP_SIZE1 && map.is_obstracle(p.x + 1, p.z) {
                     p.x += 1;
                 } else {
@@ -392,7 +468,7 @@ fn move_point(map:&Map, c:Pos2D, p:&mut Pos2D, move_dir:Option<Direction>, clock
             }
 
             if p.x == c.x + 2 && p.z >= c.z {
-                println!("C");
+                view.log(format_args!("C"));
                 if p.z > 0 && map.is_obstracle(p.x, p.z - 1) {
                     p.x -= 1;
                 } else {
@@ -401,7 +477,7 @@ fn move_point(map:&Map, c:Pos2D, p:&mut Pos2D, move_dir:Option<Direction>, clock
             }
 
             if p.z + 1 == c.z && p.x >= c.x {
-                println!("D");
+                view.log(format_args!("D"));
                 if p.x > 0 && map.is_obstracle(p.x - 1, p.z) {
                     p.x -= 1;
                 } else {
@@ -421,18 +497,18 @@ fn move_point(map:&Map, c:Pos2D, p:&mut Pos2D, move_dir:Option<Direction>, clock
 fn can_move(map:&Map, c:Pos2D, dir:Direction) -> bool {
     match dir {
         Direction::Front =>
-            c.z < MAP_SIZE2 && map.is_floor(c.x,c.z+2) && map.is_floor(c.x+1,c.z+2),
+            c.z < MAP_SIZE2 && map.fits(c.x,c.z+1,AGENT_SIZE),
         Direction::Right =>
-            c.x < MAP_SIZE2 && map.is_floor(c.x+2,c.z) && map.is_floor(c.x+2,c.z+1),
+            c.x < MAP_SIZE2 && map.fits(c.x+1,c.z,AGENT_SIZE),
         Direction::Left =>
-            c.x > 0 && map.is_floor(c.x-1,c.z) && map.is_floor(c.x-1,c.z+1),
+            c.x > 0 && map.fits(c.x-1,c.z,AGENT_SIZE),
         Direction::Back =>
-            c.z > 0 && map.is_floor(c.x,c.z-1) && map.is_floor(c.x+1,c.z-1),
+            c.z > 0 && map.fits(c.x,c.z-1,AGENT_SIZE),
     }
 }
 
 
-fn is_bridge(map:&Map, c:Pos2D, dir:Direction) -> Option<Pos2D> {
+fn is_bridge(view:&HookView, map:&Map, c:Pos2D, dir:Direction) -> Option<Pos2D> {
     match dir {
         Direction::Front => {
             if c.z < MAP_SIZE2 {
@@ -461,7 +537,7 @@ fn is_bridge(map:&Map, c:Pos2D, dir:Direction) -> Option<Pos2D> {
             }
         },
         Direction::Left => {
-            println!("LEFT {} {}",c.x,c.z);
+            view.log(format_args!("LEFT {} {}",c.x,c.z));
             if c.x > 0 {
                 if map.is_floor(c.x-1,c.z) {
                     Some(Pos2D::new(c.x-1,c.z+1))
@@ -475,16 +551,16 @@ fn is_bridge(map:&Map, c:Pos2D, dir:Direction) -> Option<Pos2D> {
             }
         },
         Direction::Back => {
-            println!("BACK {} {}",c.x,c.z);
+            view.log(format_args!("BACK {} {}",c.x,c.z));
             if c.z > 0 {
                 if map.is_floor(c.x,c.z-1) {
-                    println!("A");
+                    view.log(format_args!("A"));
                     Some(Pos2D::new(c.x+1,c.z-1))
                 }else if map.is_floor(c.x+1,c.z-1){
-                    println!("B");
+                    view.log(format_args!("B"));
                     Some(Pos2D::new(c.x,c.z-1))
                 }else{
-                    println!("C");
+                    view.log(format_args!("C"));
                     None
                 }
             }else{
@@ -495,74 +571,67 @@ fn is_bridge(map:&Map, c:Pos2D, dir:Direction) -> Option<Pos2D> {
 }
 
 
-pub fn trace_line(traces:&mut TracePool, storage:&Storage, render_sender:&mut RenderSender, map:&mut Map,
-                  a:Pos2D, b:Pos2D, trace_id:TraceID, hook_mode:HookMode) -> Result<(),Error> {
-    let obstracle=find_obstracle(render_sender, map, a,b)?;
+///Follows the line from a to b and hooks around obstacles, returns waypoints of the found route
+pub fn trace_line(view:&mut HookView, map:&Map, a:Pos2D, b:Pos2D, trace_id:TraceID, hook_mode:HookMode) -> Result<Option<Vec<Pos2D>>,Error> {
+    trace_line_at(view, map, a, b, trace_id, hook_mode, 0)
+}
+
+fn trace_line_at(view:&mut HookView, map:&Map,
+                  a:Pos2D, b:Pos2D, trace_id:TraceID, hook_mode:HookMode, depth:u32) -> Result<Option<Vec<Pos2D>>,Error> {
+    let obstracle=find_obstracle(view, map, a,b)?;
     let (dir,_,len,_) = calc_trace(a,b);
 
     match obstracle {
         Some(obstracle_pos) => {
-            try_send!(render_sender, RenderCommand::SetTraceColor(trace_id,RED));
+            view.set_trace_color(trace_id,RED)?;
+
+            //A hook, that does not get the trace around the obstacle, would repeat forever
+            if depth>=MAX_HOOK_DEPTH {
+                return ok!(None);
+            }
 
             let hooks_pos=match dir {
                 Direction::Front => {
-                    println!("Obstracle {} {}",obstracle_pos.x,obstracle_pos.z);
+                    view.log(format_args!("Obstracle {} {}",obstracle_pos.x,obstracle_pos.z));
 
                     (
-                        hook(render_sender, map, obstracle_pos, Direction::Front, true, a, b, len,hook_mode)?,
+                        hook(view, map, obstracle_pos, Direction::Front, true, a, b, len,hook_mode)?,
                         None
                     )
                 },
-                Direction::Back => {
-                    (
-                        None,None
-                    )
-                },
+                //The hook walks around obstacles in front of the trace only, other traces find no route
                 _ => (None,None)
             };
 
-            let left_traces=match hooks_pos.0 {
-                Some(c) => {
-                    Some((
-                        (a,c,add_trace(traces, storage, a,c)?),
-                        (c,b,add_trace(traces, storage, c,b)?)
-                    ))
-                },
-                None => None
-            };
+            let mut routes=Vec::new();
 
-            let right_traces=match hooks_pos.1 {
-                Some(c) => {
-                    Some((
-                        (a,c,add_trace(traces, storage, a,c)?),
-                        (c,b,add_trace(traces, storage, c,b)?)
-                    ))
-                },
-                None => None
-            };
+            for &hook_pos in [hooks_pos.0, hooks_pos.1].iter() {
+                let c=match hook_pos {
+                    Some(c) => c,
+                    None => continue,
+                };
 
-            match left_traces {
-                Some((t1,t2)) => {
-                    trace_line(traces, storage, render_sender, map, t1.0,t1.1,t1.2, HookMode::MostRemote)?;
-                    trace_line(traces, storage, render_sender, map, t2.0,t2.1,t2.2, HookMode::Unreachable)?;
-                }
-                None => {},
-            }
+                let t1=view.add_trace(a,c)?;
+                let t2=view.add_trace(c,b)?;
+
+                let first=trace_line_at(view, map, a, c, t1, HookMode::MostRemote, depth+1)?;
+                let second=trace_line_at(view, map, c, b, t2, HookMode::Unreachable, depth+1)?;
 
-            match right_traces {
-                Some((t1,t2)) => {
-                    trace_line(traces, storage, render_sender, map, t1.0,t1.1,t1.2, HookMode::MostRemote)?;
-                    trace_line(traces, storage, render_sender, map, t2.0,t2.1,t2.2, HookMode::Unreachable)?;
+                match (first, second) {
+                    (Some(mut route), Some(second)) => {
+                        route.extend(second.into_iter().skip(1));
+                        routes.push(route);
+                    },
+                    _ => {},
                 }
-                None => {},
             }
+
+            ok!(routes.into_iter().min_by(|r1,r2| grid::path_length(r1).partial_cmp(&grid::path_length(r2)).unwrap_or(Ordering::Equal)))
         },
         None => {
-            try_send!(render_sender, RenderCommand::SetTraceColor(trace_id,GREEN));
+            view.set_trace_color(trace_id,GREEN)?;
+
+            ok!(Some(vec![a,b]))
         },
     }
-
-    //try_send!(render_sender, RenderCommand::ClearTiles);
-
-    ok!()
-}
\ No newline at end of file
+}
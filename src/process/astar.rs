@@ -0,0 +1,62 @@
+use std;
+
+use consts::*;
+
+use std::collections::BinaryHeap;
+
+use super::Map;
+use super::algorithm::Pos2D;
+use super::grid;
+use super::grid::OpenNode;
+
+///Plain 8-directional A* over tiles, where the agent fits. Returns waypoints of the path from a to b
+pub fn find_path(map:&Map, a:Pos2D, b:Pos2D) -> Option<Vec<Pos2D>> {
+    if !grid::is_walkable(map, a.x as i32, a.z as i32) || !grid::is_walkable(map, b.x as i32, b.z as i32) {
+        return None;
+    }
+
+    let mut g=[[std::f32::INFINITY;MAP_SIZE];MAP_SIZE];
+    let mut closed=[[false;MAP_SIZE];MAP_SIZE];
+    let mut parents=[[None;MAP_SIZE];MAP_SIZE];
+    let mut open=BinaryHeap::new();
+
+    g[a.x as usize][a.z as usize]=0.0;
+    open.push(OpenNode::new(grid::octile(a,b), a));
+
+    while let Some(node)=open.pop() {
+        let c=node.pos;
+
+        if closed[c.x as usize][c.z as usize] {
+            continue;
+        }
+
+        closed[c.x as usize][c.z as usize]=true;
+
+        if c==b {
+            return Some(grid::simplify(grid::build_path(&parents, a, b)));
+        }
+
+        for &(dx,dz) in grid::DIRECTIONS.iter() {
+            if !grid::can_step(map, c, dx, dz) {
+                continue;
+            }
+
+            let n=Pos2D::new((c.x as i32 + dx) as u32, (c.z as i32 + dz) as u32);
+
+            if closed[n.x as usize][n.z as usize] {
+                continue;
+            }
+
+            let cost=if dx!=0 && dz!=0 {grid::SQRT_2} else {1.0};
+            let ng=g[c.x as usize][c.z as usize] + cost;
+
+            if ng < g[n.x as usize][n.z as usize] {
+                g[n.x as usize][n.z as usize]=ng;
+                parents[n.x as usize][n.z as usize]=Some(c);
+                open.push(OpenNode::new(ng + grid::octile(n,b), n));
+            }
+        }
+    }
+
+    None
+}
@@ -0,0 +1,132 @@
+use std;
+
+use consts::*;
+
+use std::time::Instant;
+
+use super::{Map,Tile};
+use super::algorithm::Pos2D;
+use super::grid;
+use super::Pathfinder;
+
+const QUERIES:usize = 200;
+const GENERATED_MAPS:usize = 8;
+
+///A* goes first, other pathfinders are checked against its path lengths. The hook algorithm finds any-angle
+///routes, its mismatches show how often it differs from the grid optimum
+const PATHFINDERS:[Pathfinder;4] = [Pathfinder::AStar, Pathfinder::JumpPoint, Pathfinder::FlowField, Pathfinder::Hook];
+
+///Small xorshift generator, the benchmark has to be reproducible between runs
+struct Random(u32);
+
+impl Random {
+    fn next(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0
+    }
+
+    fn below(&mut self, limit:u32) -> u32 {
+        self.next() % limit
+    }
+}
+
+fn generate_map(random:&mut Random, obstacles_percent:u32) -> Map {
+    let mut map=Map::new();
+
+    for z in 0..MAP_SIZE {
+        for x in 0..MAP_SIZE {
+            map.tiles[x][z]=if random.below(100) < obstacles_percent {
+                Tile::Wall(0)
+            }else{
+                Tile::Floor(0)
+            };
+        }
+    }
+
+    map.calc_clearance();
+    map
+}
+
+fn generate_queries(random:&mut Random, map:&Map) -> Vec<(Pos2D,Pos2D)> {
+    let mut walkable=Vec::new();
+
+    for z in 0..MAP_SIZE {
+        for x in 0..MAP_SIZE {
+            if grid::is_walkable(map, x as i32, z as i32) {
+                walkable.push(Pos2D::new(x as u32, z as u32));
+            }
+        }
+    }
+
+    if walkable.len() < 2 {
+        return Vec::new();
+    }
+
+    (0..QUERIES).map(|_| {
+        let a=walkable[random.below(walkable.len() as u32) as usize];
+        let b=walkable[random.below(walkable.len() as u32) as usize];
+        (a,b)
+    }).collect()
+}
+
+fn bench_map(name:&str, map:&Map, queries:&[(Pos2D,Pos2D)]) {
+    if queries.is_empty() {
+        println!("{}: no walkable tiles, skipped", name);
+        return;
+    }
+
+    let mut reference=Vec::with_capacity(queries.len());
+
+    for (i,pathfinder) in PATHFINDERS.iter().enumerate() {
+        let mut found=0;
+        let mut mismatches=0;
+
+        let begin=Instant::now();
+
+        for (q,&(a,b)) in queries.iter().enumerate() {
+            let length=pathfinder.find_path(map, a, b).map(|path| grid::path_length(&path));
+
+            if length.is_some() {
+                found+=1;
+            }
+
+            if i==0 {
+                reference.push(length);
+            }else{
+                let same=match (reference[q], length) {
+                    (Some(l1), Some(l2)) => (l1-l2).abs() < 0.001,
+                    (None, None) => true,
+                    _ => false,
+                };
+
+                if !same {
+                    mismatches+=1;
+                }
+            }
+        }
+
+        let elapsed=begin.elapsed();
+        let micros=elapsed.as_secs() as f64 * 1_000_000.0 + elapsed.subsec_nanos() as f64 / 1000.0;
+
+        println!("{}: {} {} queries, {} paths found, {} mismatches, total {:.0} us, {:.2} us per query",
+            name, pathfinder, queries.len(), found, mismatches, micros, micros / queries.len() as f64);
+    }
+}
+
+///Compares pathfinders on the loaded map and on generated maps with growing obstacle density
+pub fn run(map:&Map) {
+    let mut random=Random(0x2545F491);
+
+    let queries=generate_queries(&mut random, map);
+    bench_map("bundled", map, &queries);
+
+    for i in 0..GENERATED_MAPS {
+        let obstacles_percent=(i as u32)*5;
+        let generated=generate_map(&mut random, obstacles_percent);
+        let queries=generate_queries(&mut random, &generated);
+
+        bench_map(format!("generated {}% walls", obstacles_percent).as_str(), &generated, &queries);
+    }
+}
@@ -24,5 +24,10 @@ pub enum ProcessCommand {
     Shutdown,
 
     ResourcesLoaded,
-    Algorithm((u32,u32),(u32,u32))
+    Algorithm((u32,u32),(u32,u32)),
+    NextPathfinder,
+    ///Shows the shortest any-angle route of the visibility graph next to the hook traces
+    ToggleReferenceRoute,
+    ///Deletes the results of the last query, the map stays loaded
+    Reset,
 }
\ No newline at end of file
@@ -16,7 +16,23 @@ define_error!( Error,
         "Mutex has been poisoned",
 
     StorageError(storage_error:Box<storage::Error>) =>
-        "Storage error:{}"
+        "Storage error:{}",
+
+    OpenMapFileError(file_name:String) =>
+        "Can not open map \"{1}\"",
+    ReadMapFileError(file_name:String) =>
+        "Can not read map \"{1}\"",
+    ParseMapError(file_name:String, message:String) =>
+        "Can not load map \"{1}\": {2}",
+
+    OpenManifestFileError(file_name:String) =>
+        "Can not open manifest \"{1}\"",
+    ReadManifestFileError(file_name:String) =>
+        "Can not read manifest \"{1}\"",
+    ParseManifestError(file_name:String, message:String) =>
+        "Can not parse manifest \"{1}\": {2}",
+    ManifestError(file_name:String, entry:String, message:String) =>
+        "Manifest \"{1}\", entry \"{2}\": {3}"
 );
 
 
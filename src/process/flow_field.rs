@@ -0,0 +1,118 @@
+use std;
+
+use consts::*;
+
+use std::collections::BinaryHeap;
+
+use super::Map;
+use super::algorithm::Pos2D;
+use super::grid;
+use super::grid::OpenNode;
+
+///Integration field of path costs toward one goal and a direction of the next step for every tile.
+///Tiles are agent positions, like in the other pathfinders: the (min x, min z) corner of the agent footprint
+#[derive(Clone)]
+pub struct FlowField {
+    goal:Pos2D,
+    costs:[[f32;MAP_SIZE];MAP_SIZE],
+    directions:[[Option<(i32,i32)>;MAP_SIZE];MAP_SIZE],
+}
+
+impl FlowField {
+    pub fn new(map:&Map, goal:Pos2D) -> Self {
+        let mut flow_field=FlowField {
+            goal,
+            costs:[[std::f32::INFINITY;MAP_SIZE];MAP_SIZE],
+            directions:[[None;MAP_SIZE];MAP_SIZE],
+        };
+
+        if !grid::is_walkable(map, goal.x as i32, goal.z as i32) {
+            return flow_field;
+        }
+
+        let mut closed=[[false;MAP_SIZE];MAP_SIZE];
+        let mut open=BinaryHeap::new();
+
+        flow_field.costs[goal.x as usize][goal.z as usize]=0.0;
+        open.push(OpenNode::new(0.0, goal));
+
+        //Dijkstra from the goal, steps are symmetric, so costs toward the goal are the same
+        while let Some(node)=open.pop() {
+            let c=node.pos;
+
+            if closed[c.x as usize][c.z as usize] {
+                continue;
+            }
+
+            closed[c.x as usize][c.z as usize]=true;
+
+            for &(dx,dz) in grid::DIRECTIONS.iter() {
+                if !grid::can_step(map, c, dx, dz) {
+                    continue;
+                }
+
+                let n=Pos2D::new((c.x as i32 + dx) as u32, (c.z as i32 + dz) as u32);
+
+                if closed[n.x as usize][n.z as usize] {
+                    continue;
+                }
+
+                let cost=if dx!=0 && dz!=0 {grid::SQRT_2} else {1.0};
+                let ng=flow_field.costs[c.x as usize][c.z as usize] + cost;
+
+                if ng < flow_field.costs[n.x as usize][n.z as usize] {
+                    flow_field.costs[n.x as usize][n.z as usize]=ng;
+                    flow_field.directions[n.x as usize][n.z as usize]=Some((-dx,-dz));
+                    open.push(OpenNode::new(ng, n));
+                }
+            }
+        }
+
+        flow_field
+    }
+
+    pub fn get_goal(&self) -> Pos2D {
+        self.goal
+    }
+
+    ///Direction of the next step from x,z. None for the goal, unreachable tiles and tiles outside of the map
+    pub fn sample(&self, x:u32, z:u32) -> Option<(i32,i32)> {
+        if x as usize >= MAP_SIZE || z as usize >= MAP_SIZE {
+            return None;
+        }
+
+        self.directions[x as usize][z as usize]
+    }
+
+    ///Cost of the path from x,z to the goal, None if the goal can not be reached
+    pub fn cost(&self, x:u32, z:u32) -> Option<f32> {
+        if x as usize >= MAP_SIZE || z as usize >= MAP_SIZE {
+            return None;
+        }
+
+        let cost=self.costs[x as usize][z as usize];
+
+        if cost.is_finite() {
+            Some(cost)
+        }else{
+            None
+        }
+    }
+
+    ///Follows the field from a and returns waypoints of the path to the goal
+    pub fn path(&self, a:Pos2D) -> Option<Vec<Pos2D>> {
+        if self.cost(a.x, a.z).is_none() {
+            return None;
+        }
+
+        let mut points=vec![a];
+        let mut c=a;
+
+        while let Some((dx,dz))=self.sample(c.x, c.z) {
+            c=Pos2D::new((c.x as i32 + dx) as u32, (c.z as i32 + dz) as u32);
+            points.push(c);
+        }
+
+        Some(grid::simplify(points))
+    }
+}
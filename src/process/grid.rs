@@ -0,0 +1,132 @@
+use std;
+
+use consts::*;
+
+use std::cmp::Ordering;
+
+use super::Map;
+use super::algorithm::Pos2D;
+
+pub const SQRT_2:f32 = std::f32::consts::SQRT_2;
+
+pub const DIRECTIONS:[(i32,i32);8] = [
+    (1,0), (-1,0), (0,1), (0,-1),
+    (1,1), (1,-1), (-1,1), (-1,-1)
+];
+
+///Checks, that the agent can stand with its (min x, min z) corner at x,z
+pub fn is_walkable(map:&Map, x:i32, z:i32) -> bool {
+    x>=0 && z>=0 && (x as usize)<MAP_SIZE && (z as usize)<MAP_SIZE && map.fits(x as u32, z as u32, AGENT_SIZE)
+}
+
+///Diagonal steps are allowed only when both adjacent orthogonal steps are free, so the agent never cuts corners
+pub fn can_step(map:&Map, c:Pos2D, dx:i32, dz:i32) -> bool {
+    let x=c.x as i32;
+    let z=c.z as i32;
+
+    if !is_walkable(map, x+dx, z+dz) {
+        return false;
+    }
+
+    dx==0 || dz==0 || (is_walkable(map, x+dx, z) && is_walkable(map, x, z+dz))
+}
+
+pub fn octile(a:Pos2D, b:Pos2D) -> f32 {
+    let dx=(a.x as i32 - b.x as i32).abs() as f32;
+    let dz=(a.z as i32 - b.z as i32).abs() as f32;
+
+    if dx>dz {
+        dx-dz + dz*SQRT_2
+    }else{
+        dz-dx + dx*SQRT_2
+    }
+}
+
+pub fn euclidean(a:Pos2D, b:Pos2D) -> f32 {
+    let dx=a.x as f32 - b.x as f32;
+    let dz=a.z as f32 - b.z as f32;
+
+    (dx*dx + dz*dz).sqrt()
+}
+
+pub fn path_length(points:&[Pos2D]) -> f32 {
+    points.windows(2).map(|w| euclidean(w[0],w[1])).sum()
+}
+
+///Follows parents from b back to a
+pub fn build_path(parents:&[[Option<Pos2D>;MAP_SIZE];MAP_SIZE], a:Pos2D, b:Pos2D) -> Vec<Pos2D> {
+    let mut points=vec![b];
+    let mut c=b;
+
+    while c!=a {
+        c=match parents[c.x as usize][c.z as usize] {
+            Some(parent) => parent,
+            None => break,
+        };
+
+        points.push(c);
+    }
+
+    points.reverse();
+    points
+}
+
+///Removes points, that lie on a straight segment between their neighbours
+pub fn simplify(points:Vec<Pos2D>) -> Vec<Pos2D> {
+    let direction=|a:Pos2D, b:Pos2D| {
+        ((b.x as i32 - a.x as i32).signum(), (b.z as i32 - a.z as i32).signum())
+    };
+
+    let mut simplified:Vec<Pos2D>=Vec::with_capacity(points.len());
+
+    for &p in points.iter() {
+        if simplified.len()>=2 {
+            let a=simplified[simplified.len()-2];
+            let b=simplified[simplified.len()-1];
+
+            if direction(a,b)==direction(b,p) {
+                simplified.pop();
+            }
+        }
+
+        simplified.push(p);
+    }
+
+    simplified
+}
+
+///Entry of the open list, ordered so, that BinaryHeap pops the lowest f first
+#[derive(Copy,Clone)]
+pub struct OpenNode<T> {
+    pub f:f32,
+    pub pos:T,
+}
+
+impl<T> OpenNode<T> {
+    pub fn new(f:f32, pos:T) -> Self {
+        OpenNode {
+            f,
+            pos
+        }
+    }
+}
+
+impl<T> PartialEq for OpenNode<T> {
+    fn eq(&self, other:&Self) -> bool {
+        self.f==other.f
+    }
+}
+
+impl<T> Eq for OpenNode<T> {}
+
+impl<T> PartialOrd for OpenNode<T> {
+    fn partial_cmp(&self, other:&Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for OpenNode<T> {
+    fn cmp(&self, other:&Self) -> Ordering {
+        other.f.partial_cmp(&self.f).unwrap_or(Ordering::Equal)
+    }
+}
@@ -0,0 +1,185 @@
+use std;
+
+use consts::*;
+
+use std::collections::BinaryHeap;
+
+use super::Map;
+use super::algorithm::Pos2D;
+use super::grid;
+use super::grid::OpenNode;
+
+///Jump Point Search for maps with uniform floor cost. Uses the same movement rules as astar::find_path:
+///8 directions without cutting corners. Returns waypoints of the path from a to b
+pub fn find_path(map:&Map, a:Pos2D, b:Pos2D) -> Option<Vec<Pos2D>> {
+    if !grid::is_walkable(map, a.x as i32, a.z as i32) || !grid::is_walkable(map, b.x as i32, b.z as i32) {
+        return None;
+    }
+
+    let mut g=[[std::f32::INFINITY;MAP_SIZE];MAP_SIZE];
+    let mut closed=[[false;MAP_SIZE];MAP_SIZE];
+    let mut parents:[[Option<Pos2D>;MAP_SIZE];MAP_SIZE]=[[None;MAP_SIZE];MAP_SIZE];
+    let mut open=BinaryHeap::new();
+
+    g[a.x as usize][a.z as usize]=0.0;
+    open.push(OpenNode::new(grid::octile(a,b), a));
+
+    while let Some(node)=open.pop() {
+        let c=node.pos;
+
+        if closed[c.x as usize][c.z as usize] {
+            continue;
+        }
+
+        closed[c.x as usize][c.z as usize]=true;
+
+        if c==b {
+            return Some(grid::simplify(grid::build_path(&parents, a, b)));
+        }
+
+        let parent=parents[c.x as usize][c.z as usize];
+
+        for (dx,dz) in neighbours(map, c, parent) {
+            let jump_point=match jump(map, c.x as i32 + dx, c.z as i32 + dz, dx, dz, b) {
+                Some(jump_point) => jump_point,
+                None => continue,
+            };
+
+            if closed[jump_point.x as usize][jump_point.z as usize] {
+                continue;
+            }
+
+            let ng=g[c.x as usize][c.z as usize] + grid::octile(c,jump_point);
+
+            if ng < g[jump_point.x as usize][jump_point.z as usize] {
+                g[jump_point.x as usize][jump_point.z as usize]=ng;
+                parents[jump_point.x as usize][jump_point.z as usize]=Some(c);
+                open.push(OpenNode::new(ng + grid::octile(jump_point,b), jump_point));
+            }
+        }
+    }
+
+    None
+}
+
+///Pruned directions to search from c, when it was reached from parent
+fn neighbours(map:&Map, c:Pos2D, parent:Option<Pos2D>) -> Vec<(i32,i32)> {
+    let parent=match parent {
+        Some(parent) => parent,
+        None => return grid::DIRECTIONS.iter().cloned().filter(|&(dx,dz)| grid::can_step(map, c, dx, dz)).collect(),
+    };
+
+    let x=c.x as i32;
+    let z=c.z as i32;
+    let dx=(x - parent.x as i32).signum();
+    let dz=(z - parent.z as i32).signum();
+    let walkable=|x:i32, z:i32| grid::is_walkable(map, x, z);
+
+    let mut directions=Vec::with_capacity(5);
+
+    if dx!=0 && dz!=0 {
+        if walkable(x, z+dz) {
+            directions.push((0,dz));
+        }
+
+        if walkable(x+dx, z) {
+            directions.push((dx,0));
+        }
+
+        if walkable(x, z+dz) && walkable(x+dx, z) && walkable(x+dx, z+dz) {
+            directions.push((dx,dz));
+        }
+    }else if dx!=0 {
+        let is_next=walkable(x+dx, z);
+        let is_front=walkable(x, z+1);
+        let is_back=walkable(x, z-1);
+
+        if is_next {
+            directions.push((dx,0));
+
+            if is_front && walkable(x+dx, z+1) {
+                directions.push((dx,1));
+            }
+
+            if is_back && walkable(x+dx, z-1) {
+                directions.push((dx,-1));
+            }
+        }
+
+        if is_front {
+            directions.push((0,1));
+        }
+
+        if is_back {
+            directions.push((0,-1));
+        }
+    }else{
+        let is_next=walkable(x, z+dz);
+        let is_right=walkable(x+1, z);
+        let is_left=walkable(x-1, z);
+
+        if is_next {
+            directions.push((0,dz));
+
+            if is_right && walkable(x+1, z+dz) {
+                directions.push((1,dz));
+            }
+
+            if is_left && walkable(x-1, z+dz) {
+                directions.push((-1,dz));
+            }
+        }
+
+        if is_right {
+            directions.push((1,0));
+        }
+
+        if is_left {
+            directions.push((-1,0));
+        }
+    }
+
+    directions
+}
+
+///Moves from x,z in direction dx,dz until it finds the goal, a tile with a forced neighbour or an obstacle
+fn jump(map:&Map, x:i32, z:i32, dx:i32, dz:i32, goal:Pos2D) -> Option<Pos2D> {
+    let walkable=|x:i32, z:i32| grid::is_walkable(map, x, z);
+
+    let mut x=x;
+    let mut z=z;
+
+    loop {
+        if !walkable(x,z) {
+            return None;
+        }
+
+        let c=Pos2D::new(x as u32, z as u32);
+
+        if c==goal {
+            return Some(c);
+        }
+
+        if dx!=0 && dz!=0 {
+            if jump(map, x+dx, z, dx, 0, goal).is_some() || jump(map, x, z+dz, 0, dz, goal).is_some() {
+                return Some(c);
+            }
+        }else if dx!=0 {
+            if (walkable(x, z-1) && !walkable(x-dx, z-1)) || (walkable(x, z+1) && !walkable(x-dx, z+1)) {
+                return Some(c);
+            }
+        }else{
+            if (walkable(x-1, z) && !walkable(x-1, z-dz)) || (walkable(x+1, z) && !walkable(x+1, z-dz)) {
+                return Some(c);
+            }
+        }
+
+        //Diagonal moves need both orthogonal tiles to be free
+        if !(walkable(x+dx, z) && walkable(x, z+dz)) {
+            return None;
+        }
+
+        x+=dx;
+        z+=dz;
+    }
+}
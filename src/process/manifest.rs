@@ -0,0 +1,497 @@
+use std;
+use nes::{ErrorInfo,ErrorInfoTrait};
+use serde_json;
+
+use types::*;
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+
+use serde_json::Value;
+
+use render::RenderSender;
+use render::SetSlot;
+use render::storage::{ObjectMesh,TerrainMesh,TraceMesh};
+use render::storage::{ObjectVertex,TraceVertex};
+use render::storage::face_normals;
+
+use storage::{MeshStorage,LodStorage};
+use storage::RgbaTexture;
+use storage::ObjModel;
+
+use ::Storage;
+
+use super::Error;
+
+//Order of sides in the mask of wall and hole variants, the same as render::terrain::tile_mesh uses
+const SIDES:[&str;4] = ["right", "left", "front", "back"];
+
+const SLOTS:[&str;13] = ["cursor", "cursor_a", "cursor_b", "tile", "terrain_textures", "floor", "walls", "holes", "overlay_quad", "arrow",
+    "trace_body", "trace_head", "unit"];
+
+///Slots, that the render can not draw the scene without
+const REQUIRED_SLOTS:[&str;7] = ["floor", "walls", "holes", "cursor", "trace_body", "trace_head", "overlay_quad"];
+
+enum Geometry {
+    Object(Vec<ObjectVertex>),
+    Trace(Vec<TraceVertex>),
+}
+
+enum Mesh {
+    Object(ObjectMeshID),
+    Terrain(TerrainMeshID),
+    ///16 variants of the mesh, indexed by the mask of closed sides
+    TerrainVariants(Vec<TerrainMeshID>),
+    Trace(TraceMeshID),
+    ///Meshes of all objects of an OBJ file
+    Model(Vec<ObjectMeshID>),
+}
+
+///Textures, geometry, meshes and slot assignments, read from the asset manifest
+pub struct Manifest<'a> {
+    file_name:&'a str,
+    storage:&'a Storage,
+    textures:HashMap<String,RgbaTextureID>,
+    geometry:HashMap<String,Geometry>,
+    meshes:HashMap<String,Mesh>,
+}
+
+impl<'a> Manifest<'a> {
+    ///Loads all assets of the manifest and fills render slots. Returns the number of terrain textures,
+    ///that tiles of the map can refer to
+    pub fn load(file_name:&'a str, storage:&'a Storage, render_sender:&mut RenderSender) -> Result<usize,Error> {
+        let root=read_file(file_name)?;
+
+        let mut manifest=Manifest {
+            file_name,
+            storage,
+            textures:HashMap::new(),
+            geometry:HashMap::new(),
+            meshes:HashMap::new(),
+        };
+
+        for entry in manifest.section(&root, "textures")? {
+            manifest.load_texture(entry)?;
+        }
+
+        for entry in manifest.section(&root, "geometry")? {
+            manifest.load_geometry(entry)?;
+        }
+
+        for entry in manifest.section(&root, "meshes")? {
+            manifest.load_mesh(entry)?;
+        }
+
+        let slots=match root.get("slots").and_then(|value| value.as_object()) {
+            Some(slots) => slots,
+            None => return manifest.error("slots", "section is missing or is not an object".to_string()),
+        };
+
+        for slot in REQUIRED_SLOTS.iter() {
+            if !slots.contains_key(*slot) {
+                return manifest.error(&format!("slots/{}", slot), "required slot is missing".to_string());
+            }
+        }
+
+        for (slot,value) in slots.iter() {
+            manifest.set_slot(slot, value, render_sender)?;
+        }
+
+        let terrain_textures=slots.get("terrain_textures").and_then(|value| value.as_array()).map_or(0, |names| names.len());
+
+        ok!(terrain_textures)
+    }
+
+    fn error<T>(&self, entry:&str, message:String) -> Result<T,Error> {
+        err!(Error::ManifestError, self.file_name.to_string(), entry.to_string(), message)
+    }
+
+    fn section<'v>(&self, root:&'v Value, name:&str) -> Result<&'v Vec<Value>,Error> {
+        match root.get(name).and_then(|value| value.as_array()) {
+            Some(entries) => ok!(entries),
+            None => self.error(name, "section is missing or is not an array".to_string()),
+        }
+    }
+
+    fn name(&self, section:&str, entry:&Value) -> Result<String,Error> {
+        match entry.get("name").and_then(|value| value.as_str()) {
+            Some(name) => ok!(format!("{}/{}", section, name)),
+            None => self.error(section, "entry has no name".to_string()),
+        }
+    }
+
+    fn string_field<'v>(&self, entry_name:&str, entry:&'v Value, field:&str) -> Result<&'v str,Error> {
+        match entry.get(field).and_then(|value| value.as_str()) {
+            Some(value) => ok!(value),
+            None => self.error(entry_name, format!("field \"{}\" is missing or is not a string", field)),
+        }
+    }
+
+    fn load_texture(&mut self, entry:&Value) -> Result<(),Error> {
+        let entry_name=self.name("textures", entry)?;
+        let file=self.string_field(&entry_name, entry, "file")?;
+
+        let texture_id=match RgbaTexture::load(file, self.storage) {
+            Ok(texture_id) => texture_id,
+            Err(error) => return self.error(&entry_name, format!("{}", error)),
+        };
+
+        self.textures.insert(short_name(&entry_name), texture_id);
+
+        ok!()
+    }
+
+    ///Object vertices are [x, y, z, u, v] or [x, y, z, u, v, nx, ny, nz], trace vertices are [x, y, z], y points up.
+    ///Object vertices without normals get the normals of their triangles
+    fn load_geometry(&mut self, entry:&Value) -> Result<(),Error> {
+        let entry_name=self.name("geometry", entry)?;
+
+        let vertices=match entry.get("vertices").and_then(|value| value.as_array()) {
+            Some(vertices) if !vertices.is_empty() => vertices,
+            _ => return self.error(&entry_name, "field \"vertices\" is missing or is empty".to_string()),
+        };
+
+        let mut values=Vec::with_capacity(vertices.len());
+
+        for (i,vertex) in vertices.iter().enumerate() {
+            let components=match vertex.as_array() {
+                Some(components) => components.iter().map(|component| component.as_f64().map(|c| c as f32)).collect::<Option<Vec<f32>>>(),
+                None => None,
+            };
+
+            match components {
+                Some(components) => values.push(components),
+                None => return self.error(&entry_name, format!("vertex {} is not an array of numbers", i)),
+            }
+        }
+
+        if values.len()%3!=0 {
+            return self.error(&entry_name, format!("{} vertices do not make whole triangles", values.len()));
+        }
+
+        if values.iter().any(|v| v.len()!=values[0].len()) {
+            return self.error(&entry_name, "vertices have different numbers of components".to_string());
+        }
+
+        let geometry=match values[0].len() {
+            5 => {
+                let mut vertices:Vec<ObjectVertex>=values.iter().map(|v| ObjectVertex::with_uv([v[0], v[1], v[2]], [v[3], v[4]])).collect();
+                face_normals(&mut vertices);
+
+                Geometry::Object(vertices)
+            },
+            8 => Geometry::Object(values.iter().map(|v| {
+                let mut vertex=ObjectVertex::with_uv([v[0], v[1], v[2]], [v[3], v[4]]);
+                vertex.normal=[v[5], v[6], v[7]];
+                vertex
+            }).collect()),
+            3 => Geometry::Trace(values.iter().map(|v| TraceVertex::new(v[0], v[1], v[2])).collect()),
+            len => return self.error(&entry_name, format!("vertex has {} components, expected 5 or 8 for objects or 3 for traces", len)),
+        };
+
+        self.geometry.insert(short_name(&entry_name), geometry);
+
+        ok!()
+    }
+
+    fn geometry_names(&self, entry_name:&str, entry:&Value) -> Result<Vec<String>,Error> {
+        let names:Option<Vec<String>>=entry.get("geometry").and_then(|value| value.as_array())
+            .and_then(|names| names.iter().map(|name| name.as_str().map(|name| name.to_string())).collect());
+
+        match names {
+            Some(names) => ok!(names),
+            None => self.error(entry_name, "field \"geometry\" is missing or is not an array of names".to_string()),
+        }
+    }
+
+    fn object_vertices(&self, entry_name:&str, names:&[String]) -> Result<Vec<ObjectVertex>,Error> {
+        let mut buffer=Vec::new();
+
+        for name in names.iter() {
+            match self.geometry.get(name) {
+                Some(&Geometry::Object(ref vertices)) => buffer.extend_from_slice(vertices),
+                Some(&Geometry::Trace(_)) => return self.error(entry_name, format!("geometry \"{}\" has trace vertices", name)),
+                None => return self.error(entry_name, format!("geometry \"{}\" does not exist", name)),
+            }
+        }
+
+        ok!(buffer)
+    }
+
+    fn trace_vertices(&self, entry_name:&str, names:&[String]) -> Result<Vec<TraceVertex>,Error> {
+        let mut buffer=Vec::new();
+
+        for name in names.iter() {
+            match self.geometry.get(name) {
+                Some(&Geometry::Trace(ref vertices)) => buffer.extend_from_slice(vertices),
+                Some(&Geometry::Object(_)) => return self.error(entry_name, format!("geometry \"{}\" has object vertices", name)),
+                None => return self.error(entry_name, format!("geometry \"{}\" does not exist", name)),
+            }
+        }
+
+        ok!(buffer)
+    }
+
+    fn load_mesh(&mut self, entry:&Value) -> Result<(),Error> {
+        let entry_name=self.name("meshes", entry)?;
+        let mesh_type=self.string_field(&entry_name, entry, "type")?;
+
+        if mesh_type=="model" {
+            let meshes=self.load_model(&entry_name, entry)?;
+            self.meshes.insert(short_name(&entry_name), Mesh::Model(meshes));
+
+            return ok!();
+        }
+
+        let names=match (mesh_type, entry.get("lods")) {
+            ("object", Some(_)) => Vec::new(),
+            _ => self.geometry_names(&entry_name, entry)?,
+        };
+
+        let mesh=match mesh_type {
+            "object" => {
+                let texture=self.string_field(&entry_name, entry, "texture")?;
+
+                let texture_id=match self.textures.get(texture) {
+                    Some(&texture_id) => texture_id,
+                    None => return self.error(&entry_name, format!("texture \"{}\" does not exist", texture)),
+                };
+
+                let mut lods=Vec::new();
+
+                for (names,distance) in self.lods(&entry_name, entry, names)? {
+                    let buffer=self.object_vertices(&entry_name, &names)?;
+                    lods.push((self.storage.load_lod(buffer)?, distance));
+                }
+
+                Mesh::Object(self.storage.load_mesh(ObjectMesh::with_lods(lods, texture_id))?)
+            },
+            "terrain" => {
+                match entry.get("sides") {
+                    Some(sides) => Mesh::TerrainVariants(self.load_terrain_variants(&entry_name, &names, sides)?),
+                    None => {
+                        let buffer=self.object_vertices(&entry_name, &names)?;
+                        let lod_id=self.storage.load_lod(buffer)?;
+
+                        Mesh::Terrain(self.storage.load_mesh(TerrainMesh::new(lod_id))?)
+                    }
+                }
+            },
+            "trace" => {
+                let buffer=self.trace_vertices(&entry_name, &names)?;
+                let lod_id=self.storage.load_lod(buffer)?;
+
+                Mesh::Trace(self.storage.load_mesh(TraceMesh::new(lod_id))?)
+            },
+            _ => return self.error(&entry_name, format!("unknown type \"{}\", expected object, terrain, trace or model", mesh_type)),
+        };
+
+        self.meshes.insert(short_name(&entry_name), mesh);
+
+        ok!()
+    }
+
+    ///Distance, up to which the LOD is used. The farthest LOD may omit it
+    fn lod_distance(&self, entry_name:&str, lod:&Value) -> Result<f32,Error> {
+        match lod.get("distance") {
+            Some(distance) => match distance.as_f64() {
+                Some(distance) => ok!(distance as f32),
+                None => self.error(entry_name, "LOD distance is not a number".to_string()),
+            },
+            None => ok!(std::f32::INFINITY),
+        }
+    }
+
+    ///Geometry of every LOD of an object mesh. A mesh without "lods" has one LOD with its "geometry"
+    fn lods(&self, entry_name:&str, entry:&Value, names:Vec<String>) -> Result<Vec<(Vec<String>,f32)>,Error> {
+        let lods=match entry.get("lods") {
+            Some(lods) => match lods.as_array() {
+                Some(lods) => lods,
+                None => return self.error(entry_name, "field \"lods\" is not an array".to_string()),
+            },
+            None => return ok!(vec![(names, std::f32::INFINITY)]),
+        };
+
+        let mut result=Vec::with_capacity(lods.len());
+
+        for lod in lods.iter() {
+            result.push((self.geometry_names(entry_name, lod)?, self.lod_distance(entry_name, lod)?));
+        }
+
+        ok!(result)
+    }
+
+    ///Meshes of all parts of an OBJ model. LOD files are matched by part names, every file has to contain the same parts
+    fn load_model(&self, entry_name:&str, entry:&Value) -> Result<Vec<ObjectMeshID>,Error> {
+        let files=match entry.get("lods") {
+            Some(lods) => {
+                let lods=match lods.as_array() {
+                    Some(lods) => lods,
+                    None => return self.error(entry_name, "field \"lods\" is not an array".to_string()),
+                };
+
+                let mut files=Vec::with_capacity(lods.len());
+
+                for lod in lods.iter() {
+                    files.push((self.string_field(entry_name, lod, "file")?, self.lod_distance(entry_name, lod)?));
+                }
+
+                files
+            },
+            None => vec![(self.string_field(entry_name, entry, "file")?, std::f32::INFINITY)],
+        };
+
+        let mut parts:Vec<(String,RgbaTextureID,Vec<(ObjectLodID,f32)>)>=Vec::new();
+
+        for (i,&(file,distance)) in files.iter().enumerate() {
+            let loaded=match ObjModel::load(file, self.storage) {
+                Ok(loaded) => loaded,
+                Err(error) => return self.error(entry_name, format!("{}", error)),
+            };
+
+            if i>0 && loaded.len()!=parts.len() {
+                return self.error(entry_name, format!("\"{}\" has {} parts, expected {}", file, loaded.len(), parts.len()));
+            }
+
+            for part in loaded {
+                if i==0 {
+                    parts.push((part.name, part.texture, vec![(part.lod, distance)]));
+                    continue;
+                }
+
+                match parts.iter_mut().find(|&&mut (ref name,_,_)| *name==part.name) {
+                    Some(&mut (_,_,ref mut lods)) => lods.push((part.lod, distance)),
+                    None => return self.error(entry_name, format!("\"{}\" has unknown part \"{}\"", file, part.name)),
+                }
+            }
+        }
+
+        let mut meshes=Vec::with_capacity(parts.len());
+
+        for (_,texture_id,lods) in parts {
+            meshes.push(self.storage.load_mesh(ObjectMesh::with_lods(lods, texture_id))?);
+        }
+
+        ok!(meshes)
+    }
+
+    ///Walls and holes close the sides, where the neighbour tile is of another kind
+    fn load_terrain_variants(&self, entry_name:&str, names:&[String], sides:&Value) -> Result<Vec<TerrainMeshID>,Error> {
+        let mut side_names=Vec::with_capacity(SIDES.len());
+
+        for side in SIDES.iter() {
+            match sides.get(*side).and_then(|value| value.as_str()) {
+                Some(name) => side_names.push(name.to_string()),
+                None => return self.error(entry_name, format!("side \"{}\" is missing", side)),
+            }
+        }
+
+        let mut variants=Vec::with_capacity(16);
+
+        for mask in 0..16 {
+            let mut buffer=self.object_vertices(entry_name, names)?;
+
+            for (i,side_name) in side_names.iter().enumerate() {
+                if mask & (1<<i) > 0 {
+                    buffer.extend(self.object_vertices(entry_name, &[side_name.clone()])?);
+                }
+            }
+
+            let lod_id=self.storage.load_lod(buffer)?;
+            variants.push(self.storage.load_mesh(TerrainMesh::new(lod_id))?);
+        }
+
+        ok!(variants)
+    }
+
+    fn set_slot(&self, slot:&str, value:&Value, render_sender:&mut RenderSender) -> Result<(),Error> {
+        let entry_name=format!("slots/{}", slot);
+
+        if !SLOTS.contains(&slot) {
+            return self.error(&entry_name, "unknown slot".to_string());
+        }
+
+        if slot=="terrain_textures" {
+            let names=match value.as_array() {
+                Some(names) => names,
+                None => return self.error(&entry_name, "slot expects an array of texture names".to_string()),
+            };
+
+            for (i,name) in names.iter().enumerate() {
+                let texture_id=match name.as_str().and_then(|name| self.textures.get(name)) {
+                    Some(&texture_id) => texture_id,
+                    None => return self.error(&entry_name, format!("texture {} does not exist", name)),
+                };
+
+                try_send![render_sender, SetSlot::TerrainTexture(i,texture_id).into()];
+            }
+
+            return ok!();
+        }
+
+        let mesh=match value.as_str().and_then(|name| self.meshes.get(name)) {
+            Some(mesh) => mesh,
+            None => return self.error(&entry_name, format!("mesh {} does not exist", value)),
+        };
+
+        let set_slot=match (slot, mesh) {
+            ("cursor", &Mesh::Object(mesh_id)) => SetSlot::Cursor(mesh_id),
+            ("cursor_a", &Mesh::Object(mesh_id)) => SetSlot::CursorA(mesh_id),
+            ("cursor_b", &Mesh::Object(mesh_id)) => SetSlot::CursorB(mesh_id),
+            ("tile", &Mesh::Object(mesh_id)) => SetSlot::Tile(mesh_id),
+            ("floor", &Mesh::Terrain(mesh_id)) => SetSlot::FloorMesh(mesh_id),
+            ("walls", &Mesh::TerrainVariants(ref variants)) => {
+                for (i,&mesh_id) in variants.iter().enumerate() {
+                    try_send![render_sender, SetSlot::WallMesh(i,mesh_id).into()];
+                }
+
+                return ok!();
+            },
+            ("holes", &Mesh::TerrainVariants(ref variants)) => {
+                for (i,&mesh_id) in variants.iter().enumerate() {
+                    try_send![render_sender, SetSlot::HoleMesh(i,mesh_id).into()];
+                }
+
+                return ok!();
+            },
+            ("overlay_quad", &Mesh::Trace(mesh_id)) => SetSlot::OverlayQuad(mesh_id),
+            ("arrow", &Mesh::Trace(mesh_id)) => SetSlot::Arrow(mesh_id),
+            ("trace_body", &Mesh::Trace(mesh_id)) => SetSlot::TraceBody(mesh_id),
+            ("trace_head", &Mesh::Trace(mesh_id)) => SetSlot::TraceHead(mesh_id),
+            ("unit", &Mesh::Model(ref meshes)) => SetSlot::Unit(meshes.clone()),
+            _ => return self.error(&entry_name, format!("mesh {} has wrong type for the slot", value)),
+        };
+
+        try_send![render_sender, set_slot.into()];
+
+        ok!()
+    }
+}
+
+///"meshes/wall" -> "wall"
+fn short_name(entry_name:&str) -> String {
+    match entry_name.find('/') {
+        Some(i) => entry_name[i+1..].to_string(),
+        None => entry_name.to_string(),
+    }
+}
+
+fn read_file(file_name:&str) -> Result<Value,Error> {
+    let mut file = match File::open(file_name) {
+        Ok(file) => file,
+        Err(_) => return err!(Error::OpenManifestFileError, file_name.to_string()),
+    };
+
+    let mut text=String::new();
+
+    match file.read_to_string(&mut text) {
+        Ok(_) => {},
+        Err(_) => return err!(Error::ReadManifestFileError, file_name.to_string()),
+    }
+
+    match serde_json::from_str(&text) {
+        Ok(root) => ok!(root),
+        Err(error) => err!(Error::ParseManifestError, file_name.to_string(), format!("{}", error)),
+    }
+}
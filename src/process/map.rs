@@ -1,5 +1,10 @@
+use nes::{ErrorInfo,ErrorInfoTrait};
+
 use consts::MAP_SIZE;
 
+use super::Error;
+use super::tiled;
+
 #[derive(Clone,Copy)]
 pub enum Tile {
     Air,
@@ -11,6 +16,8 @@ pub enum Tile {
 pub struct Map {
     pub tiles:[[Tile;MAP_SIZE];MAP_SIZE],
     pub marks:[[u32;MAP_SIZE];MAP_SIZE],
+    ///Side of the largest square of floor tiles, that has this tile as its (min x, min z) corner
+    pub clearance:[[u32;MAP_SIZE];MAP_SIZE],
     last_mark:u32,
 }
 
@@ -19,10 +26,156 @@ impl Map {
         Map {
             tiles:[[Tile::Air;MAP_SIZE];MAP_SIZE],
             marks:[[0;MAP_SIZE];MAP_SIZE],
+            clearance:[[0;MAP_SIZE];MAP_SIZE],
             last_mark:0,
         }
     }
 
+    pub fn load(file_name:&str) -> Result<Self,Error> {
+        let mut map=if file_name.ends_with(".json") {
+            tiled::load_json(file_name)?
+        }else if file_name.ends_with(".tmx") {
+            tiled::load_tmx(file_name)?
+        }else{
+            Self::load_txt(file_name)?
+        };
+
+        map.calc_clearance();
+
+        ok!(map)
+    }
+
+    fn load_txt(file_name:&str) -> Result<Self,Error> {
+        use std::io::{BufReader,BufRead};
+        use std::fs::File;
+
+        let f = match File::open(file_name) {
+            Ok(f) => f,
+            Err(_) => return err!(Error::OpenMapFileError, file_name.to_string()),
+        };
+        let reader = BufReader::new(f);
+
+        let mut map=Map::new();
+
+        for (z,line_res) in reader.lines().enumerate() {
+            let line=match line_res{
+                Ok(line) => line,
+                Err(_) => return err!(Error::ReadMapFileError, file_name.to_string())
+            };
+
+            if z>=MAP_SIZE {
+                break;
+            }
+
+            let chars:Vec<char>=line.chars().collect();
+
+            if chars.len()<MAP_SIZE*2 {
+                return err!(Error::ParseMapError, file_name.to_string(), format!("line {} is shorter than {} tiles", z+1, MAP_SIZE));
+            }
+
+            for x in 0..MAP_SIZE {
+                let index=chars[x*2+1].to_digit(10).unwrap_or(0) as usize;
+
+                let tile=match chars[x*2] {
+                    'w' => Tile::Wall(index),
+                    'f' => Tile::Floor(index),
+                    'h' => Tile::Hole(index),
+                    _ => Tile::Air,
+                };
+
+                map.tiles[x][z]=tile;
+            }
+        }
+
+        ok!(map)
+    }
+
+    ///Texture indices of tiles come from the map file, they are checked against the textures of the manifest
+    pub fn check_textures(&self, file_name:&str, terrain_textures:usize) -> Result<(),Error> {
+        for z in 0..MAP_SIZE {
+            for x in 0..MAP_SIZE {
+                let index=match self.tiles[x][z] {
+                    Tile::Air => continue,
+                    Tile::Floor(index) | Tile::Wall(index) | Tile::Hole(index) => index,
+                };
+
+                if index>=terrain_textures {
+                    return err!(Error::ParseMapError, file_name.to_string(),
+                        format!("tile {},{} has texture index {}, only {} terrain textures are available", x, z, index, terrain_textures));
+                }
+            }
+        }
+
+        ok!()
+    }
+
+    ///Returns the side of the square with the tile in its (max x, max z) corner, where clearance was recomputed
+    pub fn set_tile(&mut self, x:usize, z:usize, tile:Tile) -> usize {
+        self.tiles[x][z]=tile;
+
+        //Clearance of a tile depends only on tiles with greater x and z, so only squares, that cover the tile,
+        //can change. They are walked ring by ring, until no square of the ring reaches the tile
+        let mut ring=0;
+
+        loop {
+            let mut reaches=false;
+
+            //Column of the ring first, the row depends on it
+            if x>=ring {
+                let top=if z>=ring {z-ring+1} else {0};
+
+                for cz in (top..z+1).rev() {
+                    reaches|=self.update_ring_clearance(x-ring, cz, ring);
+                }
+            }
+
+            if z>=ring {
+                for cx in (x.saturating_sub(ring)..x+1).rev() {
+                    reaches|=self.update_ring_clearance(cx, z-ring, ring);
+                }
+            }
+
+            ring+=1;
+
+            if !reaches {
+                return ring;
+            }
+        }
+    }
+
+    ///Recomputes the tile on the ring and tells, if its square reached the edited tile before or after the edit
+    fn update_ring_clearance(&mut self, x:usize, z:usize, ring:usize) -> bool {
+        let old=self.clearance[x][z];
+        self.update_clearance(x,z);
+
+        old.max(self.clearance[x][z]) as usize > ring
+    }
+
+    pub fn calc_clearance(&mut self) {
+        for z in (0..MAP_SIZE).rev() {
+            for x in (0..MAP_SIZE).rev() {
+                self.update_clearance(x,z);
+            }
+        }
+    }
+
+    fn update_clearance(&mut self, x:usize, z:usize) {
+        use std::cmp::min;
+
+        self.clearance[x][z]=if !self.tiles[x][z].is_floor() {
+            0
+        }else if x==MAP_SIZE-1 || z==MAP_SIZE-1 {
+            1
+        }else{
+            1 + min(self.clearance[x+1][z], min(self.clearance[x][z+1], self.clearance[x+1][z+1]))
+        };
+    }
+
+    ///Checks, that square agent of side size with (min x, min z) corner at x,z stands on floor only
+    pub fn fits(&self, x:u32, z:u32, size:u32) -> bool {
+        self.clearance[x as usize][z as usize] >= size
+    }
+
     pub fn is_floor(&self, x:u32, z:u32) -> bool {
         self.tiles[x as usize][z as usize].is_floor()
     }
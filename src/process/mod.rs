@@ -11,7 +11,29 @@ pub use self::commands::ProcessCommand;
 pub mod map;
 pub use self::map::{Map,Tile};
 
+pub mod tiled;
+
+pub mod manifest;
+pub use self::manifest::Manifest;
+
 pub mod trace;
 pub use self::trace::TracePool;
 
-pub mod algorithm;
\ No newline at end of file
+pub mod algorithm;
+
+pub mod grid;
+
+pub mod astar;
+
+pub mod jps;
+
+pub mod flow_field;
+pub use self::flow_field::FlowField;
+
+pub mod visibility;
+pub use self::visibility::VisibilityGraph;
+
+pub mod pathfinder;
+pub use self::pathfinder::Pathfinder;
+
+pub mod bench;
\ No newline at end of file
@@ -0,0 +1,57 @@
+use std;
+
+use types::*;
+
+use super::Map;
+use super::algorithm::{self, Pos2D, HookView, HookMode};
+use super::{astar,jps};
+use super::FlowField;
+use super::VisibilityGraph;
+
+#[derive(Debug,Copy,Clone,Eq,PartialEq)]
+pub enum Pathfinder {
+    Hook,
+    AStar,
+    JumpPoint,
+    FlowField,
+    VisibilityGraph,
+}
+
+impl Pathfinder {
+    pub fn next(&self) -> Self {
+        match *self {
+            Pathfinder::Hook => Pathfinder::AStar,
+            Pathfinder::AStar => Pathfinder::JumpPoint,
+            Pathfinder::JumpPoint => Pathfinder::FlowField,
+            Pathfinder::FlowField => Pathfinder::VisibilityGraph,
+            Pathfinder::VisibilityGraph => Pathfinder::Hook,
+        }
+    }
+
+    ///Runs the pathfinder and returns waypoints of the path. The hook algorithm runs hidden here,
+    ///the viewer calls algorithm::trace_line itself to draw the search
+    pub fn find_path(&self, map:&Map, a:Pos2D, b:Pos2D) -> Option<Vec<Pos2D>> {
+        match *self {
+            Pathfinder::Hook => {
+                //The hidden view sends nothing, so there is no error to report
+                algorithm::trace_line(&mut HookView::Hidden, map, a, b, TraceID::zeroed(), HookMode::Unreachable).unwrap_or(None)
+            },
+            Pathfinder::AStar => astar::find_path(map, a, b),
+            Pathfinder::JumpPoint => jps::find_path(map, a, b),
+            Pathfinder::FlowField => FlowField::new(map, b).path(a),
+            Pathfinder::VisibilityGraph => VisibilityGraph::new(map).find_path(a, b),
+        }
+    }
+}
+
+impl std::fmt::Display for Pathfinder{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self{
+            Pathfinder::Hook => write!(f, "Hook"),
+            Pathfinder::AStar => write!(f, "A*"),
+            Pathfinder::JumpPoint => write!(f, "Jump Point Search"),
+            Pathfinder::FlowField => write!(f, "Flow field"),
+            Pathfinder::VisibilityGraph => write!(f, "Visibility graph"),
+        }
+    }
+}
@@ -27,8 +27,14 @@ use super::Error;
 use super::ProcessCommand;
 use super::Map;
 use super::Tile;
+use super::Manifest;
 use super::TracePool;
+use super::Pathfinder;
+use super::FlowField;
+use super::VisibilityGraph;
+use super::grid;
 use super::algorithm::*;
+use super::bench;
 
 pub type ProcessSender = reactor::Sender<ThreadSource,ProcessCommand>;
 pub type ProcessReceiver = reactor::Receiver<ThreadSource,ProcessCommand>;
@@ -42,6 +48,10 @@ pub struct Process {
     storage:Storage,
     map:Option<Map>,
     traces:TracePool,
+    pathfinder:Pathfinder,
+    visibility_graph:Option<VisibilityGraph>,
+    show_reference_route:bool,
+    terrain_textures:usize,
 }
 
 impl Process{
@@ -155,7 +165,11 @@ impl Process{
 
             storage,
             map:None,
-            traces
+            traces,
+            pathfinder:Pathfinder::Hook,
+            visibility_graph:None,
+            show_reference_route:false,
+            terrain_textures:0,
         };
 
         ok!(process)
@@ -175,6 +189,13 @@ impl Process{
         self.load_resources()?;
         self.create_map()?;
 
+        if std::env::args().any(|arg| arg=="--bench") {
+            match self.map {
+                Some(ref map) => bench::run(map),
+                None => {}
+            }
+        }
+
         loop {
             if self.handle_process_commands()? {
                 println!("QUIT3");
@@ -190,8 +211,23 @@ impl Process{
                 ProcessCommand::Tick => return ok!(false),
                 ProcessCommand::Shutdown => return ok!(true),
 
-                ProcessCommand::Algorithm(a,b) =>
-                    self.algorithm(a,b)?,
+                ProcessCommand::Algorithm(a,b) => {
+                    try_send!(self.render_sender, RenderCommand::AlgorithmStarted(format!("{}", self.pathfinder)));
+                    self.algorithm(a,b)?;
+                    try_send!(self.render_sender, RenderCommand::AlgorithmFinished);
+                },
+                ProcessCommand::NextPathfinder => {
+                    self.pathfinder=self.pathfinder.next();
+                    println!("Pathfinder: {}", self.pathfinder);
+                },
+                ProcessCommand::ToggleReferenceRoute => {
+                    self.show_reference_route=!self.show_reference_route;
+                    println!("Reference route: {}", if self.show_reference_route {"on"} else {"off"});
+                },
+                ProcessCommand::Reset => {
+                    self.traces.clear()?;
+                    try_send!(self.render_sender, RenderCommand::Reset);
+                },
                 _ => unreachable!()
             }
         }
@@ -212,432 +248,132 @@ impl Process{
     }
 
     fn load_resources(&mut self) -> Result<(),Error>{
-        use storage::{TextureStorage, MeshStorage, LodStorage};
-        use storage::RgbaTexture;
-
-        use render::SetSlot;
-
-        use render::storage::ObjectMesh;
-        use render::storage::ObjectVertex;
-
-        let vertex_buffer=vec![
-            ObjectVertex { pos: [ -0.5, -0.5, 0.0 ], uv: [0.0, 1.0] },
-            ObjectVertex { pos: [  0.5, -0.5, 0.0 ], uv: [1.0, 1.0] },
-            ObjectVertex { pos: [  0.0,  0.5, 0.0 ], uv: [0.5, 0.0] }
-        ];
-        let lod_id=self.storage.load_lod(vertex_buffer).unwrap();
-
-        let texture_id=RgbaTexture::load("img.png", &self.storage)?;
-        let mesh=ObjectMesh::new(
-            lod_id,
-            texture_id
-        );
-        let mesh_id=self.storage.load_mesh(mesh).unwrap();
-
-        use std::fs::{File};
-        use std::io::{Read};
-        use std::io::{Cursor};
-        use image;
-
-        self.load_cursor()?;
-        self.load_tile()?;
-        self.load_textures()?;
-        self.load_floor()?;
-        self.load_walls()?;
-        self.load_holes()?;
-
+        self.terrain_textures=Manifest::load(ASSETS_FILE_NAME, &self.storage, &mut self.render_sender)?;
 
         try_send![self.render_sender, RenderCommand::ResourcesReady];
 
         ok!()
     }
 
-    fn load_cursor(&mut self) -> Result<(),Error> {
-        use render::SetSlot;
-        use storage::{MeshStorage, LodStorage};
-        use storage::RgbaTexture;
-        use storage::TextureStorage;
-
-        use render::storage::ObjectMesh;
-        use render::storage::ObjectVertex;
-
-        let mut buffer=Vec::with_capacity(1*6);
-
-        let top=[
-            ObjectVertex{ pos:[0.0, 0.0, 0.0], uv:[0.0, 0.0]},
-            ObjectVertex{ pos:[2.0, 0.0, 0.0], uv:[1.0, 0.0]},
-            ObjectVertex{ pos:[2.0, 0.0, 2.0], uv:[1.0, 1.0]},
-            ObjectVertex{ pos:[2.0, 0.0, 2.0], uv:[1.0, 1.0]},
-            ObjectVertex{ pos:[0.0, 0.0, 2.0], uv:[0.0, 1.0]},
-            ObjectVertex{ pos:[0.0, 0.0, 0.0], uv:[0.0, 0.0]},
-        ];
-
-        buffer.extend_from_slice(&top);
-        let lod_id=self.storage.load_lod(buffer).unwrap();
-
-        //Cursor
-        let texture_id=RgbaTexture::load("textures/cursor.png", &self.storage)?;
-
-        let mesh=ObjectMesh::new(
-            lod_id,texture_id
-        );
-
-        let mesh_id=self.storage.load_mesh(mesh)?;
-
-        try_send![self.render_sender, SetSlot::Cursor(mesh_id).into()];
-
-        //CursorA
-        let texture_id=RgbaTexture::load("textures/cursor_a.png", &self.storage)?;
-
-        let mesh=ObjectMesh::new(
-            lod_id,texture_id
-        );
-
-        let mesh_id=self.storage.load_mesh(mesh)?;
-
-        try_send![self.render_sender, SetSlot::CursorA(mesh_id).into()];
-
-        //CursorB
-        let texture_id=RgbaTexture::load("textures/cursor_b.png", &self.storage)?;
-
-        let mesh=ObjectMesh::new(
-            lod_id,texture_id
-        );
-
-        let mesh_id=self.storage.load_mesh(mesh)?;
-
-        try_send![self.render_sender, SetSlot::CursorB(mesh_id).into()];
-        ok!()
-    }
-
-    fn load_tile(&mut self) -> Result<(),Error> {
-        use render::SetSlot;
-        use storage::{MeshStorage, LodStorage};
-        use storage::RgbaTexture;
-        use storage::TextureStorage;
-
-        use render::storage::ObjectMesh;
-        use render::storage::ObjectVertex;
-
-        let mut buffer=Vec::with_capacity(1*6);
-
-        let top=[
-            ObjectVertex{ pos:[0.2, 0.0, 0.2], uv:[0.0, 0.0]},
-            ObjectVertex{ pos:[0.8, 0.0, 0.2], uv:[1.0, 0.0]},
-            ObjectVertex{ pos:[0.8, 0.0, 0.8], uv:[1.0, 1.0]},
-            ObjectVertex{ pos:[0.8, 0.0, 0.8], uv:[1.0, 1.0]},
-            ObjectVertex{ pos:[0.2, 0.0, 0.8], uv:[0.0, 1.0]},
-            ObjectVertex{ pos:[0.2, 0.0, 0.2], uv:[0.0, 0.0]},
-        ];
-
-        buffer.extend_from_slice(&top);
+    fn create_map(&mut self) -> Result<(),Error> {
+        wait![self.process_receiver,
+            ProcessCommand::ResourcesLoaded => ()
+        ].unwrap();
 
-        let texture_id=RgbaTexture::load("textures/tile.png", &self.storage)?;
-        let lod_id=self.storage.load_lod(buffer).unwrap();
+        let file_name=std::env::args().skip(1).find(|arg| !arg.starts_with("--")).unwrap_or(MAP_FILE_NAME.to_string());
+        let map=Map::load(file_name.as_str())?;
+        map.check_textures(file_name.as_str(), self.terrain_textures)?;
 
-        let mesh=ObjectMesh::new(
-            lod_id,texture_id
-        );
+        try_send![self.render_sender, RenderCommand::CreateMap];
 
-        let mesh_id=self.storage.load_mesh(mesh)?;
+        for z in 0..MAP_SIZE {
+            for x in 0..MAP_SIZE {
+                thread::sleep_ms(10);
+                try_send![self.render_sender, RenderCommand::LoadTile(x,z,map.tiles[x][z]) ];
+            }
+        }
 
-        try_send![self.render_sender, SetSlot::Tile(mesh_id).into()];
+        self.visibility_graph=Some(VisibilityGraph::new(&map));
+        self.map=Some(map);
 
         ok!()
     }
 
-    fn load_textures(&mut self) -> Result<(),Error> {
-        use render::SetSlot;
-        use storage::RgbaTexture;
-        use storage::TextureStorage;
-
-        for i in 0..5 {
-            let file_name=format!("textures/terrain{}.png",i);
-            let texture_id=RgbaTexture::load(file_name.as_str(), &self.storage)?;
-
-            try_send![self.render_sender, SetSlot::TerrainTexture(i,texture_id).into()];
+    fn algorithm(&mut self, a:(u32,u32), b:(u32,u32)) -> Result<(),Error> {
+        if self.pathfinder!=Pathfinder::Hook {
+            return self.grid_algorithm(a,b);
         }
 
-        ok!()
-    }
-
-    fn load_floor(&mut self) -> Result<(),Error> {
-        use render::SetSlot;
-        use storage::{MeshStorage, LodStorage};
-
-        use render::storage::TerrainMesh;
-        use render::storage::ObjectVertex;
-
-        let mut buffer=Vec::with_capacity(1*6);
-
-        let top=[
-            ObjectVertex::new([0, 0,  0], [0, 0]),
-            ObjectVertex::new([ 1, 0,  0], [1, 0]),
-            ObjectVertex::new([ 1,  1,  0], [1, 1]),
-            ObjectVertex::new([ 1,  1,  0], [1, 1]),
-            ObjectVertex::new([0,  1,  0], [0, 1]),
-            ObjectVertex::new([0, 0,  0], [0, 0]),
-        ];
-
-        buffer.extend_from_slice(&top);
-
-        let lod_id=self.storage.load_lod(buffer).unwrap();
-        let mesh=TerrainMesh::new(
-            lod_id
-        );
+        let map=match self.map {
+            Some(ref map) => map,
+            None => panic!("No map")
+        };
 
-        let mesh_id=self.storage.load_mesh(mesh)?;
 
-        try_send![self.render_sender, SetSlot::FloorMesh(mesh_id).into()];
+        let trace_id=add_trace(&mut self.traces, Pos2D::new(a.0,a.1), Pos2D::new(b.0,b.1))?;
 
-        ok!()
-    }
+        let route=trace_line(&mut HookView::Drawn(&mut self.traces, &mut self.render_sender), map,
+                   Pos2D::new(a.0,a.1), Pos2D::new(b.0,b.1), trace_id, HookMode::Unreachable)?;
 
-    fn load_walls(&mut self) -> Result<(),Error> {
-        use render::SetSlot;
-        use storage::{MeshStorage, LodStorage};
-
-        use render::storage::TerrainMesh;
-        use render::storage::ObjectVertex;
-
-        for i in 0..16 {
-            let mut buffer=Vec::with_capacity(6*6);
-
-            let top=[
-                ObjectVertex::new([0, 0,  2], [0, 0]),
-                ObjectVertex::new([ 1, 0,  2], [1, 0]),
-                ObjectVertex::new([ 1,  1,  2], [1, 1]),
-                ObjectVertex::new([ 1,  1,  2], [1, 1]),
-                ObjectVertex::new([0,  1,  2], [0, 1]),
-                ObjectVertex::new([0, 0,  2], [0, 0]),
-            ];
-
-            let right=[
-                ObjectVertex::new([1, 0,  2], [1, 0]),
-                ObjectVertex::new([1,  1,  2], [0, 0]),
-                ObjectVertex::new([1,  1, 0], [0, 2]),
-                ObjectVertex::new([1,  1, 0], [0, 2]),
-                ObjectVertex::new([1, 0, 0], [1, 2]),
-                ObjectVertex::new([1, 0,  2], [1, 0]),
-            ];
-
-            let left=[
-                ObjectVertex::new([0, 1, 2], [1, 0]),
-                ObjectVertex::new([0, 0, 0], [0, 2]),
-                ObjectVertex::new([0, 0, 2], [0, 0]),
-                ObjectVertex::new([0, 1, 2], [1, 0]),
-                ObjectVertex::new([0, 1, 0], [1, 2]),
-                ObjectVertex::new([0, 0, 0], [0, 2]),
-            ];
-
-            let front=[
-                ObjectVertex::new([0,  1,  2], [0, 0]),
-                ObjectVertex::new([0,  1, 0], [0, 2]),
-                ObjectVertex::new([ 1,  1, 0], [1, 2]),
-                ObjectVertex::new([ 1,  1, 0], [1, 2]),
-                ObjectVertex::new([ 1,  1,  2], [1, 0]),
-                ObjectVertex::new([0,  1,  2], [0, 0]),
-            ];
-
-            let back=[
-                ObjectVertex::new([ 1, 0,  2], [0, 0]),
-                ObjectVertex::new([0, 0,  2], [1, 0]),
-                ObjectVertex::new([0, 0, 0], [1, 2]),
-                ObjectVertex::new([0, 0, 0], [1, 2]),
-                ObjectVertex::new([ 1, 0, 0], [0, 2]),
-                ObjectVertex::new([ 1, 0,  2], [0, 0]),
-            ];
-
-            buffer.extend_from_slice(&top);
-
-            if (i & 1) >0 {
-                buffer.extend_from_slice(&right);
-            }
+        //The hook walks lines tile by tile, a route, that cuts a corner, is not a path
+        let route=match (route, self.visibility_graph.as_ref()) {
+            (Some(route), Some(visibility_graph)) =>
+                if route.windows(2).all(|segment| visibility_graph.is_visible(segment[0], segment[1])) {
+                    Some(route)
+                }else{
+                    println!("Hook route is blocked, it is not reported as a path");
+                    None
+                },
+            _ => None,
+        };
 
-            if (i & 1<<1) >0 {
-                buffer.extend_from_slice(&left);
-            }
+        try_send!(self.render_sender, RenderCommand::SetPathMetrics(path_metrics(&route)));
 
-            if (i & 1<<2) >0 {
-                buffer.extend_from_slice(&front);
-            }
+        //The exact route to measure the hook algorithm against
+        if self.show_reference_route {
+            let shortest=match self.visibility_graph {
+                Some(ref visibility_graph) => visibility_graph.find_path(Pos2D::new(a.0,a.1), Pos2D::new(b.0,b.1)),
+                None => None
+            };
 
-            if (i & 1<<3) >0 {
-                buffer.extend_from_slice(&back);
+            match shortest {
+                Some(points) => {
+                    for segment in points.windows(2) {
+                        add_colored_trace(&mut self.traces, segment[0], segment[1], AQUA)?;
+                    }
+                },
+                None => {},
             }
-
-            let lod_id=self.storage.load_lod(buffer).unwrap();
-            let mesh=TerrainMesh::new(
-                lod_id
-            );
-
-            let mesh_id=self.storage.load_mesh(mesh)?;
-
-            try_send![self.render_sender, SetSlot::WallMesh(i,mesh_id).into()];
         }
 
-        ok!()
-    }
-
-    fn load_holes(&mut self) -> Result<(),Error> {
-        use render::SetSlot;
-        use storage::{MeshStorage, LodStorage};
-
-        use render::storage::TerrainMesh;
-        use render::storage::ObjectVertex;
-
-        for i in 0..16 {
-            let mut buffer=Vec::with_capacity(6*6);
-
-            let top=[
-                ObjectVertex::new([0, 0,  -2], [0, 0]),
-                ObjectVertex::new([ 1, 0,  -2], [1, 0]),
-                ObjectVertex::new([ 1,  1,  -2], [1, 1]),
-                ObjectVertex::new([ 1,  1,  -2], [1, 1]),
-                ObjectVertex::new([0,  1,  -2], [0, 1]),
-                ObjectVertex::new([0, 0,  -2], [0, 0]),
-            ];
-
-            let right=[
-                ObjectVertex::new([ 1,  1,  2-2], [1, 0]),
-                ObjectVertex::new([ 1,  1, -2], [1, 2]),
-                ObjectVertex::new([ 1, 0, -2], [0, 2]),
-
-                ObjectVertex::new([ 1, 0, 0-2], [0, 2]),
-                ObjectVertex::new([ 1, 0,  2-2], [0, 0]),
-                ObjectVertex::new([ 1,  1,  2-2], [1, 0]),
-            ];
-
-            let left=[
-                ObjectVertex::new([0, 0,  2-2], [1, 0]),
-                ObjectVertex::new([0,  1,  2-2], [0, 0]),
-                ObjectVertex::new([0,  1, 0-2], [0, 2]),
-                ObjectVertex::new([0,  1, 0-2], [0, 2]),
-                ObjectVertex::new([0, 0, 0-2], [1, 2]),
-                ObjectVertex::new([0, 0,  2-2], [1, 0]),
-            ];
-
-            let front=[
-                ObjectVertex::new([0,  1,  2-2], [1, 0]),
-                ObjectVertex::new([0,  1, 0-2], [1, 2]),
-                ObjectVertex::new([ 1,  1, 0-2], [0, 2]),
-                ObjectVertex::new([ 1,  1, 0-2], [0, 2]),
-                ObjectVertex::new([ 1,  1,  2-2], [0, 0]),
-                ObjectVertex::new([0,  1,  2-2], [1, 0]),
-            ];
-
-            let back=[
-                ObjectVertex::new([ 1, 0,  2-2], [1, 0]),
-                ObjectVertex::new([0, 0,  2-2], [0, 0]),
-                ObjectVertex::new([0, 0, 0-2], [0, 2]),
-                ObjectVertex::new([0, 0, 0-2], [0, 2]),
-                ObjectVertex::new([ 1, 0, 0-2], [1, 2]),
-                ObjectVertex::new([ 1, 0,  2-2], [1, 0]),
-            ];
-
-            buffer.extend_from_slice(&top);
-
-            if (i & 1) >0 {
-                buffer.extend_from_slice(&right);
-            }
-
-            if (i & 1<<1) >0 {
-                buffer.extend_from_slice(&left);
-            }
-
-            if (i & 1<<2) >0 {
-                buffer.extend_from_slice(&front);
-            }
-
-            if (i & 1<<3) >0 {
-                buffer.extend_from_slice(&back);
-            }
-
-            let lod_id=self.storage.load_lod(buffer).unwrap();
-            let mesh=TerrainMesh::new(
-                lod_id
-            );
-
-            let mesh_id=self.storage.load_mesh(mesh)?;
-
-            try_send![self.render_sender, SetSlot::HoleMesh(i,mesh_id).into()];
-        }
+        try_send!(self.controller_sender, ControllerCommand::AlgorithmEnd);
 
         ok!()
     }
 
-    fn create_map(&mut self) -> Result<(),Error> {
-        use std::io::{BufReader,BufRead};
-        use std::fs::File;
-        use consts::MAP_SIZE;
-
-        wait![self.process_receiver,
-            ProcessCommand::ResourcesLoaded => ()
-        ].unwrap();
-
-        let f = File::open("map.txt").unwrap();
-        let mut reader = BufReader::new(f);
-
-        let mut map=Map::new();
-
-        for (z,line_res) in reader.lines().enumerate() {
-            let line=match line_res{
-                Ok(line) => line,
-                Err(_) => break
+    fn grid_algorithm(&mut self, a:(u32,u32), b:(u32,u32)) -> Result<(),Error> {
+        let (path,flow_field)={
+            let map=match self.map {
+                Some(ref map) => map,
+                None => panic!("No map")
             };
 
-            let chars:Vec<char>=line.chars().collect();
-
-            for x in 0..MAP_SIZE {
-                let index=match chars[x*2+1] {
-                    '0' => 0,
-                    '1' => 1,
-                    '2' => 2,
-                    '3' => 3,
-                    '4' => 4,
-                    _ => 0
-                };
-
-                if index>4 {
-                    panic!("{} {}",x,z);
-                }
-
-                let tile=match chars[x*2] {
-                    'w' => Tile::Wall(index),
-                    'f' => Tile::Floor(index),
-                    'h' => Tile::Hole(index),
-                    _ => Tile::Air,
-                };
-
-                map.tiles[x][z]=tile;
+            match self.pathfinder {
+                Pathfinder::FlowField => {
+                    let flow_field=FlowField::new(map, Pos2D::new(b.0,b.1));
+                    (flow_field.path(Pos2D::new(a.0,a.1)), Some(flow_field))
+                },
+                Pathfinder::VisibilityGraph => {
+                    let path=match self.visibility_graph {
+                        Some(ref visibility_graph) => visibility_graph.find_path(Pos2D::new(a.0,a.1), Pos2D::new(b.0,b.1)),
+                        None => None
+                    };
+
+                    (path, None)
+                },
+                _ => (self.pathfinder.find_path(map, Pos2D::new(a.0,a.1), Pos2D::new(b.0,b.1)), None)
             }
+        };
+
+        match flow_field {
+            Some(flow_field) => try_send!(self.render_sender, RenderCommand::SetFlowField(flow_field)),
+            None => {}
         }
 
-        try_send![self.render_sender, RenderCommand::CreateMap];
+        try_send!(self.render_sender, RenderCommand::SetPathMetrics(path_metrics(&path)));
 
-        for z in 0..MAP_SIZE {
-            for x in 0..MAP_SIZE {
-                thread::sleep_ms(10);
-                try_send![self.render_sender, RenderCommand::LoadTile(x,z,map.tiles[x][z]) ];
+        match path {
+            Some(points) => {
+                for segment in points.windows(2) {
+                    add_colored_trace(&mut self.traces, segment[0], segment[1], GREEN)?;
+                }
+            },
+            None => {
+                println!("{}: no path", self.pathfinder);
+                add_colored_trace(&mut self.traces, Pos2D::new(a.0,a.1), Pos2D::new(b.0,b.1), RED)?;
             }
         }
 
-        self.map=Some(map);
-
-        ok!()
-    }
-
-    fn algorithm(&mut self, a:(u32,u32), b:(u32,u32)) -> Result<(),Error> {
-        let map=match self.map {
-            Some(ref mut map) => map,
-            None => panic!("No map")
-        };
-
-
-        let trace_id=add_trace(&mut self.traces, &self.storage, Pos2D::new(a.0,a.1), Pos2D::new(b.0,b.1))?;
-
-        trace_line(&mut self.traces, &self.storage, &mut self.render_sender, map,
-                   Pos2D::new(a.0,a.1), Pos2D::new(b.0,b.1), trace_id, HookMode::Unreachable)?;
         try_send!(self.controller_sender, ControllerCommand::AlgorithmEnd);
 
         ok!()
@@ -680,7 +416,7 @@ impl Process{
             }
         };
 
-        let trace_id=self.traces.insert(&self.storage, a.0+1, a.1+1, angle, len, YELLOW)?;
+        let trace_id=self.traces.insert(a.0+1, a.1+1, angle, len, YELLOW)?;
 
         ok!(trace_id)
     }
@@ -962,4 +698,10 @@ impl Process{
         ok!()
     }
     */
-}
\ No newline at end of file
+}
+fn path_metrics(path:&Option<Vec<Pos2D>>) -> Option<render::PathMetrics> {
+    path.as_ref().map(|points| render::PathMetrics {
+        length:grid::path_length(points),
+        segments:points.len().saturating_sub(1),
+    })
+}
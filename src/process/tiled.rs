@@ -0,0 +1,423 @@
+use std;
+use nes::{ErrorInfo,ErrorInfoTrait};
+use serde_json;
+use xml;
+
+use consts::MAP_SIZE;
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read,BufReader};
+
+use serde_json::Value;
+use xml::reader::{EventReader, XmlEvent};
+use xml::attribute::OwnedAttribute;
+
+use super::Error;
+use super::{Map,Tile};
+
+//Tiled stores flip flags in the highest bits of a gid
+const FLIPPED_HORIZONTALLY_FLAG:u32 = 0x80000000;
+const FLIPPED_VERTICALLY_FLAG:u32 = 0x40000000;
+const FLIPPED_DIAGONALLY_FLAG:u32 = 0x20000000;
+const GID_MASK:u32 = !(FLIPPED_HORIZONTALLY_FLAG | FLIPPED_VERTICALLY_FLAG | FLIPPED_DIAGONALLY_FLAG);
+
+//Name of the tile property, that tells the kind of the tile: floor, wall, hole or air
+const KIND_PROPERTY:&str = "kind";
+
+#[derive(Copy,Clone)]
+enum TileKind {
+    Air,
+    Floor,
+    Wall,
+    Hole
+}
+
+impl TileKind {
+    fn parse(file_name:&str, tile_id:u32, value:&str) -> Result<Self,Error> {
+        match value {
+            "air" => ok!(TileKind::Air),
+            "floor" => ok!(TileKind::Floor),
+            "wall" => ok!(TileKind::Wall),
+            "hole" => ok!(TileKind::Hole),
+            _ => err!(Error::ParseMapError, file_name.to_string(),
+                format!("tile {} has unknown {} \"{}\", expected floor, wall, hole or air", tile_id, KIND_PROPERTY, value))
+        }
+    }
+}
+
+struct Tileset {
+    first_gid:u32,
+    kinds:HashMap<u32,TileKind>,
+}
+
+impl Tileset {
+    fn new(first_gid:u32) -> Self {
+        Tileset {
+            first_gid,
+            kinds:HashMap::new()
+        }
+    }
+}
+
+struct TiledMap {
+    width:usize,
+    height:usize,
+    tilesets:Vec<Tileset>,
+    layers:Vec<Vec<u32>>,
+}
+
+impl TiledMap {
+    fn new() -> Self {
+        TiledMap {
+            width:0,
+            height:0,
+            tilesets:Vec::new(),
+            layers:Vec::new()
+        }
+    }
+
+    fn get_tile(&self, file_name:&str, gid:u32) -> Result<Option<Tile>,Error> {
+        let gid=gid & GID_MASK;
+
+        if gid==0 {
+            return ok!(None);
+        }
+
+        let tileset=match self.tilesets.iter().filter(|tileset| tileset.first_gid<=gid).max_by_key(|tileset| tileset.first_gid) {
+            Some(tileset) => tileset,
+            None => return err!(Error::ParseMapError, file_name.to_string(), format!("gid {} does not belong to any tileset", gid))
+        };
+
+        let tile_id=gid-tileset.first_gid;
+        let index=tile_id as usize;
+
+        let tile=match tileset.kinds.get(&tile_id) {
+            Some(&TileKind::Air) => Tile::Air,
+            Some(&TileKind::Floor) | None => Tile::Floor(index),
+            Some(&TileKind::Wall) => Tile::Wall(index),
+            Some(&TileKind::Hole) => Tile::Hole(index),
+        };
+
+        ok!(Some(tile))
+    }
+
+    fn into_map(self, file_name:&str) -> Result<Map,Error> {
+        if self.width!=MAP_SIZE || self.height!=MAP_SIZE {
+            return err!(Error::ParseMapError, file_name.to_string(),
+                format!("map size {}x{} is not supported, expected {}x{}", self.width, self.height, MAP_SIZE, MAP_SIZE));
+        }
+
+        if self.layers.is_empty() {
+            return err!(Error::ParseMapError, file_name.to_string(), "map has no tile layers".to_string());
+        }
+
+        let mut map=Map::new();
+
+        for layer in self.layers.iter() {
+            if layer.len()!=self.width*self.height {
+                return err!(Error::ParseMapError, file_name.to_string(),
+                    format!("tile layer has {} tiles, expected {}", layer.len(), self.width*self.height));
+            }
+
+            for (i,&gid) in layer.iter().enumerate() {
+                let x=i % self.width;
+                let z=i / self.width;
+
+                match self.get_tile(file_name, gid)? {
+                    Some(tile) => map.tiles[x][z]=tile,
+                    None => {}
+                }
+            }
+        }
+
+        ok!(map)
+    }
+}
+
+fn read_file(file_name:&str) -> Result<String,Error> {
+    let mut file = match File::open(file_name) {
+        Ok(file) => file,
+        Err(_) => return err!(Error::OpenMapFileError, file_name.to_string()),
+    };
+
+    let mut text=String::new();
+
+    match file.read_to_string(&mut text) {
+        Ok(_) => ok!(text),
+        Err(_) => err!(Error::ReadMapFileError, file_name.to_string()),
+    }
+}
+
+fn parse_csv(file_name:&str, text:&str) -> Result<Vec<u32>,Error> {
+    let mut data=Vec::with_capacity(MAP_SIZE*MAP_SIZE);
+
+    for gid in text.split(',') {
+        let gid=gid.trim();
+
+        if gid.is_empty() {
+            continue;
+        }
+
+        match gid.parse::<u32>() {
+            Ok(gid) => data.push(gid),
+            Err(_) => return err!(Error::ParseMapError, file_name.to_string(), format!("\"{}\" is not a tile gid", gid)),
+        }
+    }
+
+    ok!(data)
+}
+
+pub fn load_json(file_name:&str) -> Result<Map,Error> {
+    let text=read_file(file_name)?;
+
+    let root:Value = match serde_json::from_str(&text) {
+        Ok(root) => root,
+        Err(error) => return err!(Error::ParseMapError, file_name.to_string(), format!("{}", error)),
+    };
+
+    let field=|value:&Value, name:&str| -> Result<u64,Error> {
+        match value.get(name).and_then(|value| value.as_u64()) {
+            Some(value) => ok!(value),
+            None => err!(Error::ParseMapError, file_name.to_string(), format!("field \"{}\" is missing or is not a number", name)),
+        }
+    };
+
+    check_orientation(file_name, root.get("orientation").and_then(|value| value.as_str()))?;
+
+    if root.get("infinite").and_then(|value| value.as_bool()).unwrap_or(false) {
+        return err!(Error::ParseMapError, file_name.to_string(), "infinite maps are not supported".to_string());
+    }
+
+    let mut tiled_map=TiledMap::new();
+    tiled_map.width=field(&root,"width")? as usize;
+    tiled_map.height=field(&root,"height")? as usize;
+
+    let tilesets=match root.get("tilesets").and_then(|value| value.as_array()) {
+        Some(tilesets) => tilesets,
+        None => return err!(Error::ParseMapError, file_name.to_string(), "field \"tilesets\" is missing".to_string()),
+    };
+
+    for value in tilesets.iter() {
+        if value.get("source").is_some() {
+            return err!(Error::ParseMapError, file_name.to_string(), "external tilesets are not supported, embed the tileset into the map".to_string());
+        }
+
+        let mut tileset=Tileset::new(field(value,"firstgid")? as u32);
+
+        //Tiled 1.2 and newer: "tiles":[{"id":0, "properties":[{"name":"kind", "value":"wall"}]}]
+        match value.get("tiles").and_then(|value| value.as_array()) {
+            Some(tiles) => {
+                for tile in tiles.iter() {
+                    let tile_id=field(tile,"id")? as u32;
+
+                    let properties=match tile.get("properties").and_then(|value| value.as_array()) {
+                        Some(properties) => properties,
+                        None => continue,
+                    };
+
+                    for property in properties.iter() {
+                        if property.get("name").and_then(|value| value.as_str())==Some(KIND_PROPERTY) {
+                            let kind=property.get("value").and_then(|value| value.as_str()).unwrap_or("");
+                            tileset.kinds.insert(tile_id, TileKind::parse(file_name, tile_id, kind)?);
+                        }
+                    }
+                }
+            },
+            None => {}
+        }
+
+        //Older versions: "tileproperties":{"0":{"kind":"wall"}}
+        match value.get("tileproperties").and_then(|value| value.as_object()) {
+            Some(tile_properties) => {
+                for (tile_id, properties) in tile_properties.iter() {
+                    let tile_id=match tile_id.parse::<u32>() {
+                        Ok(tile_id) => tile_id,
+                        Err(_) => return err!(Error::ParseMapError, file_name.to_string(), format!("\"{}\" is not a tile id", tile_id)),
+                    };
+
+                    match properties.get(KIND_PROPERTY).and_then(|value| value.as_str()) {
+                        Some(kind) => {tileset.kinds.insert(tile_id, TileKind::parse(file_name, tile_id, kind)?);},
+                        None => {}
+                    }
+                }
+            },
+            None => {}
+        }
+
+        tiled_map.tilesets.push(tileset);
+    }
+
+    let layers=match root.get("layers").and_then(|value| value.as_array()) {
+        Some(layers) => layers,
+        None => return err!(Error::ParseMapError, file_name.to_string(), "field \"layers\" is missing".to_string()),
+    };
+
+    for layer in layers.iter() {
+        match layer.get("type").and_then(|value| value.as_str()) {
+            Some("tilelayer") => {},
+            Some("group") =>
+                return err!(Error::ParseMapError, file_name.to_string(), "group layers are not supported".to_string()),
+            _ => continue,
+        }
+
+        match layer.get("encoding").and_then(|value| value.as_str()) {
+            None | Some("csv") => {},
+            Some(encoding) =>
+                return err!(Error::ParseMapError, file_name.to_string(), format!("layer encoding \"{}\" is not supported, use CSV", encoding)),
+        }
+
+        let data=match layer.get("data").and_then(|value| value.as_array()) {
+            Some(data) => data,
+            None => return err!(Error::ParseMapError, file_name.to_string(), "tile layer has no data".to_string()),
+        };
+
+        let mut gids=Vec::with_capacity(data.len());
+
+        for gid in data.iter() {
+            match gid.as_u64() {
+                Some(gid) => gids.push(gid as u32),
+                None => return err!(Error::ParseMapError, file_name.to_string(), format!("{} is not a tile gid", gid)),
+            }
+        }
+
+        tiled_map.layers.push(gids);
+    }
+
+    tiled_map.into_map(file_name)
+}
+
+pub fn load_tmx(file_name:&str) -> Result<Map,Error> {
+    let file = match File::open(file_name) {
+        Ok(file) => file,
+        Err(_) => return err!(Error::OpenMapFileError, file_name.to_string()),
+    };
+
+    let attribute=|attributes:&Vec<OwnedAttribute>, name:&str| -> Option<String> {
+        attributes.iter().find(|attribute| attribute.name.local_name==name).map(|attribute| attribute.value.clone())
+    };
+
+    let number=|attributes:&Vec<OwnedAttribute>, element:&str, name:&str| -> Result<u32,Error> {
+        match attributes.iter().find(|attribute| attribute.name.local_name==name).map(|attribute| attribute.value.parse::<u32>()) {
+            Some(Ok(value)) => ok!(value),
+            _ => err!(Error::ParseMapError, file_name.to_string(), format!("attribute \"{}\" of <{}> is missing or is not a number", name, element)),
+        }
+    };
+
+    let mut tiled_map=TiledMap::new();
+
+    let mut tile_id=None;
+    let mut layer:Option<Vec<u32>>=None;
+    let mut in_data=false;
+    let mut csv=false;
+
+    for event in EventReader::new(BufReader::new(file)) {
+        let event=match event {
+            Ok(event) => event,
+            Err(error) => return err!(Error::ParseMapError, file_name.to_string(), format!("{}", error)),
+        };
+
+        match event {
+            XmlEvent::StartElement { name, attributes, .. } => {
+                match name.local_name.as_str() {
+                    "map" => {
+                        check_orientation(file_name, attribute(&attributes,"orientation").as_ref().map(|value| value.as_str()))?;
+
+                        if attribute(&attributes,"infinite").as_ref().map(|value| value.as_str())==Some("1") {
+                            return err!(Error::ParseMapError, file_name.to_string(), "infinite maps are not supported".to_string());
+                        }
+
+                        tiled_map.width=number(&attributes,"map","width")? as usize;
+                        tiled_map.height=number(&attributes,"map","height")? as usize;
+                    },
+                    "tileset" => {
+                        if attribute(&attributes,"source").is_some() {
+                            return err!(Error::ParseMapError, file_name.to_string(), "external tilesets are not supported, embed the tileset into the map".to_string());
+                        }
+
+                        tiled_map.tilesets.push(Tileset::new(number(&attributes,"tileset","firstgid")?));
+                    },
+                    "tile" if in_data => {
+                        match layer {
+                            Some(ref mut layer) => layer.push(number(&attributes,"tile","gid").unwrap_or(0)),
+                            None => {}
+                        }
+                    },
+                    "tile" =>
+                        tile_id=Some(number(&attributes,"tile","id")?),
+                    "property" => {
+                        match (tile_id, attribute(&attributes,"name")) {
+                            (Some(tile_id), Some(ref name)) if name==KIND_PROPERTY => {
+                                let kind=attribute(&attributes,"value").unwrap_or(String::new());
+                                let kind=TileKind::parse(file_name, tile_id, kind.as_str())?;
+
+                                match tiled_map.tilesets.last_mut() {
+                                    Some(tileset) => {tileset.kinds.insert(tile_id, kind);},
+                                    None => {}
+                                }
+                            },
+                            _ => {}
+                        }
+                    },
+                    "layer" =>
+                        layer=Some(Vec::with_capacity(MAP_SIZE*MAP_SIZE)),
+                    "group" =>
+                        return err!(Error::ParseMapError, file_name.to_string(), "group layers are not supported".to_string()),
+                    "data" => {
+                        if attribute(&attributes,"compression").is_some() {
+                            return err!(Error::ParseMapError, file_name.to_string(), "compressed layers are not supported, use CSV".to_string());
+                        }
+
+                        csv=match attribute(&attributes,"encoding") {
+                            None => false,
+                            Some(ref encoding) if encoding=="csv" => true,
+                            Some(encoding) =>
+                                return err!(Error::ParseMapError, file_name.to_string(), format!("layer encoding \"{}\" is not supported, use CSV", encoding)),
+                        };
+
+                        in_data=true;
+                    },
+                    "chunk" =>
+                        return err!(Error::ParseMapError, file_name.to_string(), "infinite maps are not supported".to_string()),
+                    _ => {}
+                }
+            },
+            XmlEvent::Characters(text) => {
+                if in_data && csv {
+                    match layer {
+                        Some(ref mut layer) => layer.extend(parse_csv(file_name, text.as_str())?),
+                        None => {}
+                    }
+                }
+            },
+            XmlEvent::EndElement { name } => {
+                match name.local_name.as_str() {
+                    "tile" if !in_data =>
+                        tile_id=None,
+                    "data" =>
+                        in_data=false,
+                    "layer" => {
+                        match layer.take() {
+                            Some(layer) => tiled_map.layers.push(layer),
+                            None => {}
+                        }
+                    },
+                    _ => {}
+                }
+            },
+            _ => {}
+        }
+    }
+
+    tiled_map.into_map(file_name)
+}
+
+fn check_orientation(file_name:&str, orientation:Option<&str>) -> Result<(),Error> {
+    match orientation {
+        Some("orthogonal") => ok!(),
+        Some(orientation) =>
+            err!(Error::ParseMapError, file_name.to_string(), format!("{} orientation is not supported, only orthogonal maps can be loaded", orientation)),
+        None =>
+            err!(Error::ParseMapError, file_name.to_string(), "map orientation is missing".to_string()),
+    }
+}
@@ -3,19 +3,16 @@ use nes::{ErrorInfo,ErrorInfoTrait};
 
 use types::*;
 
-use object_pool::growable::Pool;
+use object_pool::growable::{Pool,ID};
 use render::{RenderSender,RenderCommand};
-use render::storage::{TraceMesh,TraceLod};
-use render::storage::TraceVertex;
 
 use super::Error;
 
-use ::Storage;
-
-use storage::storage::{MeshStorage,LodStorage};
-
+///IDs of the traces, the render thread keeps its own pool in the same order. All traces are drawn
+///with the shared trace meshes, so a trace has no resources of its own
 pub struct TracePool {
     pool:Pool<(), ()>,
+    ids:Vec<ID>,
     render_sender:RenderSender,
 }
 
@@ -23,85 +20,28 @@ impl TracePool {
     pub fn new(render_sender:RenderSender) -> Self {
         TracePool {
             pool:Pool::new(),
+            ids:Vec::new(),
             render_sender
         }
     }
 
-    pub fn insert(&mut self, storage:&Storage,
+    pub fn insert(&mut self,
                   x:u32,
                   z:u32,
                   angle:f32,
                   len:f32,
                   color:[f32;4]
     ) -> Result<TraceID,Error> {
-        let top=[
-            TraceVertex::new(-0.05, 0.15, len),
-            TraceVertex::new(-0.05, 0.15, 0.0),
-            TraceVertex::new(0.05, 0.15, 0.0),
-            TraceVertex::new(-0.05, 0.15, len),
-            TraceVertex::new(0.05, 0.15, 0.0),
-            TraceVertex::new(0.05, 0.15, len),
-        ];
-
-        let left=[
-            TraceVertex::new(-0.05, 0.15, len),
-            TraceVertex::new(-0.05, 0.0, len),
-            TraceVertex::new(-0.05, 0.0, 0.0),
-            TraceVertex::new(-0.05, 0.15, len),
-            TraceVertex::new(-0.05, 0.0, 0.0),
-            TraceVertex::new(-0.05, 0.15, 0.0),
-        ];
-
-        let front=[
-            TraceVertex::new(-0.05, 0.15, 0.0),
-            TraceVertex::new(-0.05, 0.0, 0.0),
-            TraceVertex::new(0.05, 0.0, 0.0),
-            TraceVertex::new(-0.05, 0.15, 0.0),
-            TraceVertex::new(0.05, 0.0, 0.0),
-            TraceVertex::new(0.05, 0.15, 0.0),
-        ];
-
-        let right=[
-            TraceVertex::new(0.05, 0.15, len),
-            TraceVertex::new(0.05, 0.0, len),
-            TraceVertex::new(0.05, 0.0, 0.0),
-            TraceVertex::new(0.05, 0.15, len),
-            TraceVertex::new(0.05, 0.0, 0.0),
-            TraceVertex::new(0.05, 0.15, 0.0),
-        ];
-
-        let back=[
-            TraceVertex::new(-0.05, 0.15, len),
-            TraceVertex::new(-0.05, 0.0, len),
-            TraceVertex::new(0.05, 0.0, len),
-            TraceVertex::new(-0.05, 0.15, len),
-            TraceVertex::new(0.05, 0.0, len),
-            TraceVertex::new(0.05, 0.15, len),
-        ];
-
-        let mut buffer=Vec::with_capacity(6*6);
-        buffer.extend_from_slice(&top);
-        buffer.extend_from_slice(&left);
-        buffer.extend_from_slice(&right);
-        buffer.extend_from_slice(&front);
-        buffer.extend_from_slice(&back);
-
-        let lod_id=storage.load_lod(buffer).unwrap();
-        let mesh=TraceMesh::new(
-            lod_id
-        );
-
-        let mesh_id=storage.load_mesh(mesh)?;
-
         let trace=render::Trace::new(
             x,
             z,
             angle,
-            color,
-            mesh_id
+            len,
+            color
         );
 
         let id=self.pool.insert(());
+        self.ids.push(id);
         let id=TraceID::new(id);
 
         try_send!(self.render_sender, RenderCommand::CreateTrace(trace));
@@ -109,11 +49,27 @@ impl TracePool {
         ok!(id)
     }
 
-    pub fn delete(&mut self,id:TraceID) -> Result<(),Error>{
+    pub fn delete(&mut self, id:TraceID) -> Result<(),Error>{
+        if self.pool.get(id.get_id()).is_none() {
+            return ok!();
+        }
+
         self.pool.remove(id.get_id());
+        self.ids.retain(|&live_id| live_id!=id.get_id());
 
         try_send!(self.render_sender, RenderCommand::DeleteTrace(id));
 
         ok!()
     }
-}
\ No newline at end of file
+
+    ///Deletes all traces
+    pub fn clear(&mut self) -> Result<(),Error>{
+        let ids:Vec<ID>=self.ids.drain(..).collect();
+
+        for id in ids {
+            self.delete(TraceID::new(id))?;
+        }
+
+        ok!()
+    }
+}
@@ -0,0 +1,249 @@
+use std;
+
+use consts::*;
+
+use std::collections::BinaryHeap;
+
+use super::Map;
+use super::algorithm::Pos2D;
+use super::grid;
+use super::grid::OpenNode;
+
+const EPS:f32 = 0.0001;
+
+///Exact any-angle pathfinder. Works with agent positions, like the other pathfinders: the agent with
+///(min x, min z) corner at p occupies [p, p+AGENT_SIZE], so every wall, hole or air tile t blocks the open
+///square (t-AGENT_SIZE, t+1) of positions. Graph vertices are convex corners of the union of these squares
+pub struct VisibilityGraph {
+    obstacles:Vec<(i32,i32)>,
+    vertices:Vec<Pos2D>,
+    edges:Vec<Vec<(usize,f32)>>,
+}
+
+impl VisibilityGraph {
+    pub fn new(map:&Map) -> Self {
+        let mut obstacles=Vec::new();
+
+        for z in 0..MAP_SIZE {
+            for x in 0..MAP_SIZE {
+                if !map.tiles[x][z].is_floor() {
+                    obstacles.push((x as i32, z as i32));
+                }
+            }
+        }
+
+        let mut graph=VisibilityGraph {
+            obstacles,
+            vertices:Vec::new(),
+            edges:Vec::new(),
+        };
+
+        graph.find_vertices();
+        graph.find_edges();
+
+        graph
+    }
+
+    fn find_vertices(&mut self) {
+        let size=AGENT_SIZE as i32;
+        let mut vertices=Vec::new();
+
+        for &(tx,tz) in self.obstacles.iter() {
+            for &(x,z) in [(tx-size,tz-size), (tx+1,tz-size), (tx-size,tz+1), (tx+1,tz+1)].iter() {
+                let vertex=(x as f32, z as f32);
+
+                if self.is_blocked(vertex) {
+                    continue;
+                }
+
+                //Convex corner: exactly one of the four quadrants around the vertex is blocked
+                let blocked_quadrants=[(-0.5,-0.5), (0.5,-0.5), (-0.5,0.5), (0.5,0.5)].iter()
+                    .filter(|&&(dx,dz)| self.is_blocked((vertex.0+dx, vertex.1+dz)))
+                    .count();
+
+                if blocked_quadrants!=1 {
+                    continue;
+                }
+
+                let vertex=Pos2D::new(x as u32, z as u32);
+
+                if !vertices.contains(&vertex) {
+                    vertices.push(vertex);
+                }
+            }
+        }
+
+        self.vertices=vertices;
+    }
+
+    fn find_edges(&mut self) {
+        let mut edges=vec![Vec::new();self.vertices.len()];
+
+        for i in 0..self.vertices.len() {
+            for j in i+1..self.vertices.len() {
+                if self.is_visible(self.vertices[i], self.vertices[j]) {
+                    let len=grid::euclidean(self.vertices[i], self.vertices[j]);
+                    edges[i].push((j,len));
+                    edges[j].push((i,len));
+                }
+            }
+        }
+
+        self.edges=edges;
+    }
+
+    ///Checks, that the point is outside of the map or strictly inside of a blocked square
+    fn is_blocked(&self, p:(f32,f32)) -> bool {
+        let size=AGENT_SIZE as f32;
+        let max=(MAP_SIZE as u32 - AGENT_SIZE) as f32;
+
+        if p.0 < -EPS || p.1 < -EPS || p.0 > max+EPS || p.1 > max+EPS {
+            return true;
+        }
+
+        self.obstacles.iter().any(|&(tx,tz)| {
+            let (tx,tz)=(tx as f32, tz as f32);
+
+            p.0 > tx-size+EPS && p.0 < tx+1.0-EPS && p.1 > tz-size+EPS && p.1 < tz+1.0-EPS
+        })
+    }
+
+    ///Exact line of sight: the segment may touch blocked squares, but never enter them
+    pub fn is_visible(&self, a:Pos2D, b:Pos2D) -> bool {
+        let size=AGENT_SIZE as f32;
+        let (ax,az)=(a.x as f32, a.z as f32);
+        let (dx,dz)=(b.x as f32 - ax, b.z as f32 - az);
+
+        for &(tx,tz) in self.obstacles.iter() {
+            let (tx,tz)=(tx as f32, tz as f32);
+
+            //Liang-Barsky clipping of the segment by the closed square
+            let mut t0=0.0f32;
+            let mut t1=1.0f32;
+            let checks=[
+                (-dx, ax-(tx-size)),
+                (dx, (tx+1.0)-ax),
+                (-dz, az-(tz-size)),
+                (dz, (tz+1.0)-az),
+            ];
+
+            let mut outside=false;
+
+            for &(p,q) in checks.iter() {
+                if p==0.0 {
+                    if q<0.0 {
+                        outside=true;
+                        break;
+                    }
+                }else{
+                    let t=q/p;
+
+                    if p<0.0 {
+                        t0=t0.max(t);
+                    }else{
+                        t1=t1.min(t);
+                    }
+                }
+            }
+
+            if outside || t1-t0 <= EPS {
+                continue;
+            }
+
+            //The clipped part has positive length, it enters the square unless it lies on the border
+            let t=(t0+t1)/2.0;
+
+            if self.is_blocked((ax + dx*t, az + dz*t)) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    ///A* over the visibility graph. Returns the truly shortest any-angle path from a to b
+    pub fn find_path(&self, a:Pos2D, b:Pos2D) -> Option<Vec<Pos2D>> {
+        if self.is_blocked((a.x as f32, a.z as f32)) || self.is_blocked((b.x as f32, b.z as f32)) {
+            return None;
+        }
+
+        if self.is_visible(a,b) {
+            return Some(vec![a,b]);
+        }
+
+        //Vertices are numbered as in self.vertices, then a and b
+        let a_index=self.vertices.len();
+        let b_index=self.vertices.len()+1;
+        let position=|index:usize| {
+            if index==a_index {
+                a
+            }else if index==b_index {
+                b
+            }else{
+                self.vertices[index]
+            }
+        };
+
+        let visible_from_b:Vec<bool>=self.vertices.iter().map(|&vertex| self.is_visible(vertex,b)).collect();
+
+        let mut g=vec![std::f32::INFINITY;self.vertices.len()+2];
+        let mut closed=vec![false;self.vertices.len()+2];
+        let mut parents:Vec<Option<usize>>=vec![None;self.vertices.len()+2];
+        let mut open=BinaryHeap::new();
+
+        g[a_index]=0.0;
+        open.push(OpenNode::new(grid::euclidean(a,b), a_index));
+
+        while let Some(node)=open.pop() {
+            let c=node.pos;
+
+            if closed[c] {
+                continue;
+            }
+
+            closed[c]=true;
+
+            if c==b_index {
+                let mut points=vec![b];
+                let mut index=b_index;
+
+                while let Some(parent)=parents[index] {
+                    points.push(position(parent));
+                    index=parent;
+                }
+
+                points.reverse();
+                return Some(points);
+            }
+
+            let mut neighbours:Vec<(usize,f32)>=if c==a_index {
+                self.vertices.iter().enumerate()
+                    .filter(|&(_,&vertex)| self.is_visible(a,vertex))
+                    .map(|(i,&vertex)| (i, grid::euclidean(a,vertex)))
+                    .collect()
+            }else{
+                self.edges[c].clone()
+            };
+
+            if c!=a_index && visible_from_b[c] {
+                neighbours.push((b_index, grid::euclidean(position(c),b)));
+            }
+
+            for (n,len) in neighbours {
+                if closed[n] {
+                    continue;
+                }
+
+                let ng=g[c]+len;
+
+                if ng < g[n] {
+                    g[n]=ng;
+                    parents[n]=Some(c);
+                    open.push(OpenNode::new(ng + grid::euclidean(position(n),b), n));
+                }
+            }
+        }
+
+        None
+    }
+}
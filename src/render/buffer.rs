@@ -0,0 +1,51 @@
+use nes::{ErrorInfo,ErrorInfoTrait};
+use gfx;
+use gfx_gl;
+
+use gfx::Factory;
+use gfx::traits::Pod;
+
+use super::Error;
+use super::Encoder;
+
+const MIN_CAPACITY:usize = 64;
+
+///Vertex buffer, that is filled again every frame. It is replaced by a larger one only, when the data does not fit
+pub struct DynamicBuffer<T> {
+    buffer:Option<gfx::handle::Buffer<gfx_gl::Resources, T>>,
+    capacity:usize,
+}
+
+impl<T:Pod> DynamicBuffer<T> {
+    pub fn new() -> Self {
+        DynamicBuffer {
+            buffer:None,
+            capacity:0,
+        }
+    }
+
+    ///Enqueues the upload of the data, draws enqueued after it see the data
+    pub fn upload(&mut self, gfx_factory:&mut gfx_gl::Factory, encoder:&mut Encoder, data:&[T]) -> Result<gfx::handle::Buffer<gfx_gl::Resources, T>,Error> {
+        if self.buffer.is_none() || data.len()>self.capacity {
+            let capacity=data.len().max(self.capacity*2).max(MIN_CAPACITY);
+
+            let buffer=match gfx_factory.create_buffer(capacity, gfx::buffer::Role::Vertex, gfx::memory::Usage::Dynamic, gfx::memory::Bind::empty()) {
+                Ok(buffer) => buffer,
+                Err(error) => return err!(Error::BufferError, format!("can not create buffer: {:?}", error)),
+            };
+
+            self.buffer=Some(buffer);
+            self.capacity=capacity;
+        }
+
+        let buffer=match self.buffer {
+            Some(ref buffer) => buffer.clone(),
+            None => unreachable!(),
+        };
+
+        match encoder.update_buffer(&buffer, data, 0) {
+            Ok(_) => ok!(buffer),
+            Err(error) => err!(Error::BufferError, format!("can not update buffer: {:?}", error)),
+        }
+    }
+}
@@ -6,19 +6,19 @@ pub struct Camera {
     pub camera_matrix:Matrix4,
     pub camera_position:Pos3D,
 
-    pub perspective_matrix:Matrix4,
+    pub projection_matrix:Matrix4,
 }
 
 impl Camera {
     pub fn new(
         camera_matrix:Matrix4,
         camera_position:Pos3D,
-        perspective_matrix:Matrix4
+        projection_matrix:Matrix4
     ) -> Self {
         Camera {
             camera_matrix,
             camera_position,
-            perspective_matrix
+            projection_matrix
         }
     }
 }
\ No newline at end of file
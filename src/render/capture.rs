@@ -0,0 +1,55 @@
+use nes::{ErrorInfo,ErrorInfoTrait};
+use gfx_gl;
+use image;
+
+use super::Error;
+
+const GL_READ_FRAMEBUFFER:u32 = 0x8CA8;
+const GL_BACK:u32 = 0x0405;
+const GL_PACK_ALIGNMENT:u32 = 0x0D05;
+const GL_RGBA:u32 = 0x1908;
+const GL_UNSIGNED_BYTE:u32 = 0x1401;
+
+///Pixels of the presented frame. Targets::final_color is the window back buffer, gfx can not copy
+///from it, so it is read with glReadPixels after the encoder has been flushed and before the swap
+pub struct Capture {
+    width:u32,
+    height:u32,
+    pixels:Vec<u8>,
+}
+
+impl Capture {
+    pub fn read(gfx_device:&mut gfx_gl::Device, width:u32, height:u32) -> Self {
+        let mut pixels=vec![0u8; (width*height*4) as usize];
+
+        unsafe {
+            gfx_device.with_gl(|gl| {
+                gl.BindFramebuffer(GL_READ_FRAMEBUFFER, 0);
+                gl.ReadBuffer(GL_BACK);
+                gl.PixelStorei(GL_PACK_ALIGNMENT, 1);
+                gl.ReadPixels(0, 0, width as i32, height as i32, GL_RGBA, GL_UNSIGNED_BYTE, pixels.as_mut_ptr() as *mut _);
+            });
+        }
+
+        Capture {
+            width,
+            height,
+            pixels
+        }
+    }
+
+    pub fn save(&self, file_name:&str) -> Result<(),Error> {
+        let row_size=self.width as usize*4;
+        let mut data=Vec::with_capacity(self.pixels.len());
+
+        //OpenGL rows go from the bottom to the top
+        for row in self.pixels.chunks(row_size).rev() {
+            data.extend_from_slice(row);
+        }
+
+        match image::save_buffer(file_name, &data[..], self.width, self.height, image::RGBA(8)) {
+            Ok(_) => ok!(),
+            Err(error) => err!(Error::CaptureError, format!("can not save \"{}\": {}", file_name, error)),
+        }
+    }
+}
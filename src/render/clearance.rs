@@ -0,0 +1,139 @@
+use gfx;
+use gfx_gl;
+
+use consts::*;
+
+use gfx::traits::FactoryExt;
+
+use cgmath::Matrix4;
+use cgmath::SquareMatrix;
+
+use render;
+use process::Map;
+
+use super::Encoder;
+use super::Storage;
+use super::Targets;
+use super::terrain::CHUNK_SIZE;
+use super::pipelines::TraceVertex;
+
+const CHUNKS:usize = (MAP_SIZE + CHUNK_SIZE - 1) / CHUNK_SIZE;
+const CLEARANCE_Y:f32 = 0.03;
+///Gap between the quads of neighbour tiles
+const INSET:f32 = 0.05;
+
+//Clearance overlay colors for clearance 1, 2 and 3 or more
+const CLEARANCE_COLORS: [[f32; 4]; 3] = [
+    [0.8, 0.1, 0.1, 0.4],
+    [0.8, 0.8, 0.1, 0.4],
+    [0.1, 0.8, 0.1, 0.4],
+];
+
+///Quads of all tiles of the chunk with the same clearance color
+struct Batch {
+    color:[f32;4],
+    vertex_buffer:gfx::handle::Buffer<gfx_gl::Resources, TraceVertex>,
+    slice:gfx::Slice<gfx_gl::Resources>,
+}
+
+struct Chunk {
+    dirty:bool,
+    batches:Vec<Batch>,
+}
+
+///Clearance debug overlay, split into the same chunks as the terrain and drawn with one call per chunk and color
+pub struct ClearanceOverlay {
+    chunks:Vec<Chunk>,
+}
+
+impl ClearanceOverlay {
+    pub fn new() -> Self {
+        let chunks=(0..CHUNKS*CHUNKS).map(|_| Chunk {
+            dirty:true,
+            batches:Vec::new(),
+        }).collect();
+
+        ClearanceOverlay {
+            chunks
+        }
+    }
+
+    ///Marks chunks of the square with the tile in its (max x, max z) corner, as returned by Map::set_tile
+    pub fn invalidate(&mut self, x:usize, z:usize, side:usize) {
+        let (min_x, min_z)=((x+1).saturating_sub(side), (z+1).saturating_sub(side));
+
+        for chunk_z in min_z/CHUNK_SIZE..z/CHUNK_SIZE+1 {
+            for chunk_x in min_x/CHUNK_SIZE..x/CHUNK_SIZE+1 {
+                self.chunks[chunk_z*CHUNKS + chunk_x].dirty=true;
+            }
+        }
+    }
+
+    ///Rebuilds dirty chunks, must be called before the draw
+    pub fn update(&mut self, map:&Map, gfx_factory:&mut gfx_gl::Factory) {
+        for i in 0..self.chunks.len() {
+            if !self.chunks[i].dirty {
+                continue;
+            }
+
+            self.chunks[i].batches=build_chunk(map, gfx_factory, i%CHUNKS, i/CHUNKS);
+            self.chunks[i].dirty=false;
+        }
+    }
+
+    pub fn draw(&self, storage:&Storage, encoder:&mut Encoder, targets:&Targets) {
+        let model_matrix=Matrix4::<f32>::identity();
+
+        for chunk in self.chunks.iter() {
+            for batch in chunk.batches.iter() {
+                let data = render::pipelines::TracePipeline::Data {
+                    globals: storage.trace_globals.clone(),
+                    model_matrix: model_matrix.into(),
+                    color: batch.color,
+                    vbuf: batch.vertex_buffer.clone(),
+
+                    color_target: targets.final_color.clone(),
+                    depth_target: targets.final_depth.clone()
+                };
+
+                encoder.draw(&batch.slice, &storage.trace_pso.pso, &data);
+                storage.count_draw_call();
+            }
+        }
+    }
+}
+
+fn build_chunk(map:&Map, gfx_factory:&mut gfx_gl::Factory, chunk_x:usize, chunk_z:usize) -> Vec<Batch> {
+    let mut geometry:Vec<Vec<TraceVertex>>=CLEARANCE_COLORS.iter().map(|_| Vec::new()).collect();
+
+    for z in chunk_z*CHUNK_SIZE..MAP_SIZE.min((chunk_z+1)*CHUNK_SIZE) {
+        for x in chunk_x*CHUNK_SIZE..MAP_SIZE.min((chunk_x+1)*CHUNK_SIZE) {
+            let clearance=map.clearance[x][z] as usize;
+
+            if clearance==0 {
+                continue;
+            }
+
+            let vertices=&mut geometry[clearance.min(CLEARANCE_COLORS.len())-1];
+            let (x0,z0)=(x as f32 + INSET, z as f32 + INSET);
+            let (x1,z1)=(x as f32 + 1.0 - INSET, z as f32 + 1.0 - INSET);
+
+            vertices.push(TraceVertex::new(x0, CLEARANCE_Y, z0));
+            vertices.push(TraceVertex::new(x1, CLEARANCE_Y, z0));
+            vertices.push(TraceVertex::new(x1, CLEARANCE_Y, z1));
+            vertices.push(TraceVertex::new(x1, CLEARANCE_Y, z1));
+            vertices.push(TraceVertex::new(x0, CLEARANCE_Y, z1));
+            vertices.push(TraceVertex::new(x0, CLEARANCE_Y, z0));
+        }
+    }
+
+    geometry.into_iter().zip(CLEARANCE_COLORS.iter()).filter(|&(ref vertices,_)| !vertices.is_empty()).map(|(vertices,&color)| {
+        let (vertex_buffer, slice) = gfx_factory.create_vertex_buffer_with_slice(&vertices[..], ());
+
+        Batch {
+            color,
+            vertex_buffer,
+            slice
+        }
+    }).collect()
+}
@@ -10,8 +10,9 @@ use::Camera;
 use super::storage::{ObjectMesh,TerrainMesh, TraceMesh};
 use super::pipelines::{ObjectVertex, TraceVertex};
 use super::Trace;
+use super::PathMetrics;
 
-use process::Tile;
+use process::{Tile,FlowField};
 
 pub enum RenderCommand {
     ThreadCrash(ThreadSource),
@@ -24,7 +25,6 @@ pub enum RenderCommand {
     SupervisorReady,
     SupervisorFinished,
 
-    Tick,
     Shutdown,
 
     ResizeWindow(u32,u32),
@@ -32,6 +32,11 @@ pub enum RenderCommand {
     LoadTexture(LoadTexture),
     LoadMesh(LoadMesh),
     LoadLod(LoadLod),
+    DeleteTexture(DeleteTexture),
+    DeleteMesh(DeleteMesh),
+    DeleteLod(DeleteLod),
+    ///File of the loaded texture, watched by the hot reload
+    WatchTexture(String, RgbaTextureID),
     SetSlot(SetSlot),
 
     ResourcesReady,
@@ -39,13 +44,41 @@ pub enum RenderCommand {
     LoadTile(usize, usize, Tile),
 
     MoveCursor(u32,u32),
+    ///Tile under the mouse, labeled when the grid is shown
+    HoverTile(Option<(u32,u32)>),
     SetCursorA(Option<(u32,u32)>),
     SetCursorB(Option<(u32,u32)>),
     CreateTrace(Trace),
     DeleteTrace(TraceID),
     SetTraceColor(TraceID,[f32;4]),
+    ///Multiplies the thickness of all traces
+    ScaleTraceThickness(f32),
+    ///The next AddTile begins a new walk, it is not a step from the previous tile
+    StartWalk,
     AddTile(u32,u32,bool),
-    ClearTiles
+    ClearTiles,
+    ///Switches the heatmap between the last run and all runs since reset
+    ToggleHeatmapScope,
+    ToggleGrid,
+    ///Clears A, B, visited tiles, the cursor tile, the flow field and the path metrics. Traces are deleted by the process
+    Reset,
+
+    ToggleClearance,
+    SetFlowField(FlowField),
+    ToggleFlowField,
+    ///Cycles solid, wireframe and flat colour
+    NextRenderMode,
+    ToggleAmbientOcclusion,
+
+    Screenshot(String),
+    PrintStats,
+    ToggleHud,
+    ToggleCapture,
+    ///Name of the pathfinder
+    AlgorithmStarted(String),
+    AlgorithmFinished,
+    ///None if there is no path
+    SetPathMetrics(Option<PathMetrics>),
 }
 
 pub enum LoadTexture {
@@ -71,8 +104,8 @@ impl Into<RenderCommand> for LoadMesh {
 }
 
 pub enum LoadLod {
-    Object(Vec<ObjectVertex>, ObjectLodID),
-    Trace(Vec<TraceVertex>, TraceLodID)
+    Object(Vec<ObjectVertex>, Vec<u32>, ObjectLodID),
+    Trace(Vec<TraceVertex>, Vec<u32>, TraceLodID)
 }
 
 impl Into<RenderCommand> for LoadLod {
@@ -81,6 +114,39 @@ impl Into<RenderCommand> for LoadLod {
     }
 }
 
+pub enum DeleteTexture {
+    RGBA(RgbaTextureID),
+}
+
+impl Into<RenderCommand> for DeleteTexture {
+    fn into(self) -> RenderCommand {
+        RenderCommand::DeleteTexture(self)
+    }
+}
+
+pub enum DeleteMesh {
+    Object(ObjectMeshID),
+    Terrain(TerrainMeshID),
+    Trace(TraceMeshID),
+}
+
+impl Into<RenderCommand> for DeleteMesh {
+    fn into(self) -> RenderCommand {
+        RenderCommand::DeleteMesh(self)
+    }
+}
+
+pub enum DeleteLod {
+    Object(ObjectLodID),
+    Trace(TraceLodID)
+}
+
+impl Into<RenderCommand> for DeleteLod {
+    fn into(self) -> RenderCommand {
+        RenderCommand::DeleteLod(self)
+    }
+}
+
 pub enum SetSlot {
     Cursor(ObjectMeshID),
     CursorA(ObjectMeshID),
@@ -90,6 +156,11 @@ pub enum SetSlot {
     FloorMesh(TerrainMeshID),
     WallMesh(usize,TerrainMeshID),
     HoleMesh(usize,TerrainMeshID),
+    OverlayQuad(TraceMeshID),
+    Arrow(TraceMeshID),
+    TraceBody(TraceMeshID),
+    TraceHead(TraceMeshID),
+    Unit(Vec<ObjectMeshID>),
 }
 
 impl Into<RenderCommand> for SetSlot {
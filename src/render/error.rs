@@ -5,6 +5,7 @@ use glutin;
 use reactor;
 
 use types::ThreadSource;
+use storage::Handle;
 
 define_error!( Error,
     ThreadCrash(thread:ThreadSource) =>
@@ -23,12 +24,24 @@ define_error!( Error,
         "Create PSO error:{1}",
     CreateTextureError(texture_error:Box<gfx::CombinedError>) =>
         "Create Texture Error: {1}",
-    NoTexture() => //TODO
-        "Texture does not exists",
-    NoMesh() => //TODO
-        "Mesh does not exists",
-    NoLod() => //TODO
-        "Lod does not exists",
+    NoTexture(handle:Handle) =>
+        "Texture {1} does not exist",
+    NoMesh(handle:Handle) =>
+        "Mesh {1} does not exist",
+    NoLod(handle:Handle) =>
+        "Lod {1} does not exist",
+    TextureReplaced(handle:Handle) =>
+        "Texture {1} has replaced a texture, that was not deleted",
+    MeshReplaced(handle:Handle) =>
+        "Mesh {1} has replaced a mesh, that was not deleted",
+    LodReplaced(handle:Handle) =>
+        "Lod {1} has replaced a lod, that was not deleted",
+    BufferError(message:String) =>
+        "Buffer error: {1}",
+    CaptureError(message:String) =>
+        "Capture error: {1}",
+    FontError(message:String) =>
+        "Font error: {1}",
     Other(message:String) =>
         "{}"
 );
@@ -0,0 +1,151 @@
+use nes::{ErrorInfo,ErrorInfoTrait};
+use gfx;
+use gfx_gl;
+
+use consts::{MAP_SIZE,clamp_agent_position};
+use location::*;
+
+use gfx::traits::FactoryExt;
+
+use cgmath::SquareMatrix;
+
+use camera::picking;
+
+use render;
+
+use super::Error;
+use super::Storage;
+use super::Encoder;
+use super::Targets;
+use super::Text;
+use super::pipelines::TraceVertex;
+
+///Slightly above the floor, so the lines do not fight with it
+const GRID_Y:f32 = 0.01;
+const GRID_COLOR:[f32;4] = [0.0, 0.0, 0.0, 0.45];
+///Distance of the edge labels from the map
+const LABEL_OFFSET:f32 = 0.5;
+const LABEL_COLOR:[f32;4] = [1.0, 1.0, 1.0, 1.0];
+const HOVER_COLOR:[f32;4] = [1.0, 0.9, 0.3, 1.0];
+const HOVER_PANEL_COLOR:[f32;4] = [0.0, 0.0, 0.0, 0.6];
+const HOVER_PADDING:f32 = 3.0;
+
+///Lines between tiles with tile coordinates along the back and left edges of the map and under the mouse.
+///Toggled with G, hidden by default
+pub struct Grid {
+    visible:bool,
+    hover:Option<(u32,u32)>,
+    vertex_buffer:gfx::handle::Buffer<gfx_gl::Resources, TraceVertex>,
+    slice:gfx::Slice<gfx_gl::Resources>,
+}
+
+impl Grid {
+    pub fn new(gfx_factory:&mut gfx_gl::Factory) -> Self {
+        let size=MAP_SIZE as f32;
+        let mut vertices=Vec::with_capacity((MAP_SIZE+1)*4);
+
+        for i in 0..MAP_SIZE+1 {
+            let i=i as f32;
+
+            vertices.push(TraceVertex::new(i, GRID_Y, 0.0));
+            vertices.push(TraceVertex::new(i, GRID_Y, size));
+            vertices.push(TraceVertex::new(0.0, GRID_Y, i));
+            vertices.push(TraceVertex::new(size, GRID_Y, i));
+        }
+
+        let (vertex_buffer, slice) = gfx_factory.create_vertex_buffer_with_slice(&vertices[..], ());
+
+        Grid {
+            visible:false,
+            hover:None,
+            vertex_buffer,
+            slice
+        }
+    }
+
+    pub fn toggle(&mut self) -> bool {
+        self.visible=!self.visible;
+        self.visible
+    }
+
+    ///Tile under the mouse, None if the mouse is not over the map
+    pub fn set_hover(&mut self, hover:Option<(u32,u32)>) {
+        self.hover=hover;
+    }
+
+    pub fn draw(&self, storage:&Storage, encoder:&mut Encoder, targets:&Targets) -> Result<(),Error> {
+        if !self.visible {
+            return ok!();
+        }
+
+        let data = render::pipelines::TracePipeline::Data {
+            globals: storage.trace_globals.clone(),
+            model_matrix: Matrix4::identity().into(),
+            color: GRID_COLOR,
+            vbuf: self.vertex_buffer.clone(),
+
+            color_target: targets.final_color.clone(),
+            depth_target: targets.final_depth.clone()
+        };
+
+        encoder.draw(&self.slice, &storage.trace_pso.line_pso, &data);
+        storage.count_draw_call();
+
+        ok!()
+    }
+
+    ///Labels are placed by projecting the points of the map to the screen
+    pub fn draw_labels(&self, text:&mut Text, storage:&Storage, encoder:&mut Encoder, targets:&Targets, screen_size:(u32,u32),
+        proj_view_matrix:&Matrix4
+    ) {
+        if !self.visible {
+            return;
+        }
+
+        let mut vertices=Vec::new();
+
+        {
+            let mut label=|label:&str, point:Pos3D, color:[f32;4], panel:bool| {
+                let (x,y)=match picking::project(proj_view_matrix, screen_size.0, screen_size.1, point) {
+                    Some(position) => position,
+                    None => return,
+                };
+
+                let width=text.measure(label);
+                let (left,top)=((x-width/2.0).round(), (y-text.line_height/2.0).round());
+
+                if panel {
+                    text.layout_rect(left-HOVER_PADDING, top-HOVER_PADDING, width+HOVER_PADDING*2.0, text.line_height+HOVER_PADDING*2.0,
+                        HOVER_PANEL_COLOR, &mut vertices);
+                }
+
+                text.layout(label, left, top, color, &mut vertices);
+            };
+
+            for i in 0..MAP_SIZE {
+                let center=i as f32 + 0.5;
+
+                label(&format!("{}", i), Pos3D::new(center, 0.0, -LABEL_OFFSET), LABEL_COLOR, false);
+                label(&format!("{}", i), Pos3D::new(-LABEL_OFFSET, 0.0, center), LABEL_COLOR, false);
+            }
+
+            //A and B are set at the cursor, that is clamped, so the label names the clamped tile too
+            match self.hover {
+                Some((x,z)) => {
+                    let (cursor_x, cursor_z)=clamp_agent_position(x,z);
+
+                    let hover_label=if (cursor_x, cursor_z)==(x,z) {
+                        format!("{}, {}", x, z)
+                    }else{
+                        format!("{}, {} -> {}, {}", x, z, cursor_x, cursor_z)
+                    };
+
+                    label(&hover_label, Pos3D::new(x as f32 + 0.5, 0.0, z as f32 + 0.5), HOVER_COLOR, true);
+                },
+                None => {},
+            }
+        }
+
+        text.draw(storage, encoder, targets, screen_size, &vertices);
+    }
+}
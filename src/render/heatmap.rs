@@ -0,0 +1,217 @@
+use nes::{ErrorInfo,ErrorInfoTrait};
+
+use consts::MAP_SIZE;
+
+use cgmath::{Matrix4,Vector3};
+
+use super::Error;
+use super::Storage;
+use super::Slots;
+use super::Encoder;
+use super::Targets;
+use super::Text;
+
+const HEAT_Y:f32 = 0.025;
+const HEAT_ALPHA:f32 = 0.55;
+///Colours of the ramp from the cheapest to the most expensive tile
+const RAMP:[[f32;3];4] = [
+    [0.1, 0.3, 1.0],
+    [0.1, 0.9, 0.3],
+    [1.0, 0.9, 0.1],
+    [1.0, 0.15, 0.1],
+];
+
+const MARGIN:f32 = 8.0;
+const BAR_WIDTH:f32 = 160.0;
+const BAR_HEIGHT:f32 = 12.0;
+const BAR_STEPS:usize = 32;
+const PANEL_COLOR:[f32;4] = [0.0, 0.0, 0.0, 0.5];
+const TEXT_COLOR:[f32;4] = [0.95, 0.95, 0.95, 1.0];
+
+#[derive(Copy, Clone)]
+struct HeatCell {
+    visits:u32,
+    cost:f32,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum HeatmapScope {
+    LastRun,
+    ///All runs since the last reset
+    AllRuns,
+}
+
+///Visits of the tiles, that the algorithm has walked, and the accumulated cost of the steps onto them.
+///A step costs its length, so diagonal steps weigh more
+pub struct Heatmap {
+    last_run:Vec<HeatCell>,
+    all_runs:Vec<HeatCell>,
+    ///Previous tile of the walked tiles and of the probe points, they are two separate walks
+    previous:[Option<(u32,u32)>;2],
+    scope:HeatmapScope,
+}
+
+impl Heatmap {
+    pub fn new() -> Self {
+        let empty=HeatCell {
+            visits:0,
+            cost:0.0,
+        };
+
+        Heatmap {
+            last_run:vec![empty; MAP_SIZE*MAP_SIZE],
+            all_runs:vec![empty; MAP_SIZE*MAP_SIZE],
+            previous:[None, None],
+            scope:HeatmapScope::LastRun,
+        }
+    }
+
+    pub fn visit(&mut self, x:u32, z:u32, walked:bool) {
+        let walk=if walked {0} else {1};
+
+        //The first tile of a walk costs as a straight step
+        let cost=match self.previous[walk] {
+            Some((px,pz)) => {
+                let (dx,dz)=(x as f32 - px as f32, z as f32 - pz as f32);
+                (dx*dx + dz*dz).sqrt()
+            },
+            None => 1.0,
+        };
+
+        self.previous[walk]=Some((x,z));
+
+        let i=x as usize*MAP_SIZE + z as usize;
+
+        for cell in [&mut self.last_run[i], &mut self.all_runs[i]].iter_mut() {
+            cell.visits+=1;
+            cell.cost+=cost;
+        }
+    }
+
+    ///Called, when the algorithm starts
+    pub fn clear_last_run(&mut self) {
+        for cell in self.last_run.iter_mut() {
+            cell.visits=0;
+            cell.cost=0.0;
+        }
+
+        self.start_walk();
+    }
+
+    ///The hook algorithm walks every trace and every hook separately, the next tile is not a step from the last one
+    pub fn start_walk(&mut self) {
+        self.previous=[None, None];
+    }
+
+    pub fn reset(&mut self) {
+        self.clear_last_run();
+
+        for cell in self.all_runs.iter_mut() {
+            cell.visits=0;
+            cell.cost=0.0;
+        }
+    }
+
+    pub fn toggle_scope(&mut self) -> HeatmapScope {
+        self.scope=match self.scope {
+            HeatmapScope::LastRun => HeatmapScope::AllRuns,
+            HeatmapScope::AllRuns => HeatmapScope::LastRun,
+        };
+
+        self.scope
+    }
+
+    fn cells(&self) -> &[HeatCell] {
+        match self.scope {
+            HeatmapScope::LastRun => &self.last_run,
+            HeatmapScope::AllRuns => &self.all_runs,
+        }
+    }
+
+    ///Largest number of visits and cost of a tile
+    fn max(&self) -> (u32,f32) {
+        self.cells().iter().fold((0,0.0), |(visits,cost),cell| (visits.max(cell.visits), cost.max(cell.cost)))
+    }
+
+    pub fn draw(&self, storage:&Storage, encoder:&mut Encoder, targets:&Targets, slots:&Slots) -> Result<(),Error> {
+        let (_,max_cost)=self.max();
+
+        if max_cost<=0.0 {
+            return ok!();
+        }
+
+        let mesh=storage.trace_meshes.get(slots.overlay_quad)?;
+
+        for (i,cell) in self.cells().iter().enumerate() {
+            if cell.visits==0 {
+                continue;
+            }
+
+            let (x,z)=(i/MAP_SIZE, i%MAP_SIZE);
+            let model_matrix=Matrix4::from_translation(Vector3::new(x as f32, HEAT_Y, z as f32));
+
+            mesh.draw_matrix(storage, encoder, targets, model_matrix, ramp(cell.cost/max_cost, HEAT_ALPHA))?;
+        }
+
+        ok!()
+    }
+
+    ///Ramp with the cost range in the bottom left corner
+    pub fn draw_legend(&self, text:&mut Text, storage:&Storage, encoder:&mut Encoder, targets:&Targets, screen_size:(u32,u32)) {
+        let (max_visits,max_cost)=self.max();
+
+        if max_visits==0 {
+            return;
+        }
+
+        let title=match self.scope {
+            HeatmapScope::LastRun => "Heatmap: last run",
+            HeatmapScope::AllRuns => "Heatmap: all runs",
+        };
+
+        let visits=format!("Max visits: {}", max_visits);
+        let (min_label, max_label)=("0".to_string(), format!("{:.1}", max_cost));
+
+        let width=BAR_WIDTH.max(text.measure(title)).max(text.measure(&visits));
+        let height=text.line_height*3.0 + BAR_HEIGHT;
+
+        let left=MARGIN;
+        let top=screen_size.1 as f32 - MARGIN*3.0 - height;
+
+        let mut vertices=Vec::new();
+        text.layout_rect(left, top, width+MARGIN*2.0, height+MARGIN*2.0, PANEL_COLOR, &mut vertices);
+
+        let (x,mut y)=(left+MARGIN, top+MARGIN);
+
+        text.layout(title, x, y, TEXT_COLOR, &mut vertices);
+        y+=text.line_height;
+
+        let step_width=BAR_WIDTH/BAR_STEPS as f32;
+
+        for i in 0..BAR_STEPS {
+            let t=i as f32/(BAR_STEPS-1) as f32;
+            text.layout_rect(x + step_width*i as f32, y, step_width, BAR_HEIGHT, ramp(t, 1.0), &mut vertices);
+        }
+
+        y+=BAR_HEIGHT;
+
+        text.layout(&min_label, x, y, TEXT_COLOR, &mut vertices);
+        text.layout(&max_label, x + BAR_WIDTH - text.measure(&max_label), y, TEXT_COLOR, &mut vertices);
+        y+=text.line_height;
+
+        text.layout(&visits, x, y, TEXT_COLOR, &mut vertices);
+
+        text.draw(storage, encoder, targets, screen_size, &vertices);
+    }
+}
+
+///Colour of the value from 0 to 1, interpolated between the stops of the ramp
+fn ramp(t:f32, alpha:f32) -> [f32;4] {
+    let position=t.max(0.0).min(1.0)*(RAMP.len()-1) as f32;
+    let i=(position.floor() as usize).min(RAMP.len()-2);
+    let k=position-i as f32;
+
+    let (a,b)=(RAMP[i], RAMP[i+1]);
+
+    [a[0]+(b[0]-a[0])*k, a[1]+(b[1]-a[1])*k, a[2]+(b[2]-a[2])*k, alpha]
+}
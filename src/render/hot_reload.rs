@@ -0,0 +1,229 @@
+use std;
+
+use types::*;
+use consts::*;
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use storage::RgbaTexture as ImageFile;
+
+use super::Storage;
+use super::storage::TextureStorage;
+use super::pipelines::{link_object_pso, link_trace_pso, link_trace_instanced_pso, link_text_pso, link_flat_pso};
+use super::pipelines::{OBJECT_SHADERS, TRACE_SHADERS, TRACE_INSTANCED_SHADERS, TEXT_SHADERS, FLAT_SHADERS};
+
+///Files are checked once in this interval, not every frame
+const POLL_INTERVAL_MS:u64 = 500;
+
+#[derive(Copy,Clone)]
+enum Program {
+    Object,
+    Trace,
+    TraceInstanced,
+    Text,
+    Flat,
+}
+
+impl Program {
+    fn name(&self) -> &'static str {
+        match *self {
+            Program::Object => "object",
+            Program::Trace => "trace",
+            Program::TraceInstanced => "instanced trace",
+            Program::Text => "text",
+            Program::Flat => "flat",
+        }
+    }
+}
+
+const PROGRAMS:[(Program,(&str,&str));5] = [
+    (Program::Object, OBJECT_SHADERS),
+    (Program::Trace, TRACE_SHADERS),
+    (Program::TraceInstanced, TRACE_INSTANCED_SHADERS),
+    (Program::Text, TEXT_SHADERS),
+    (Program::Flat, FLAT_SHADERS),
+];
+
+///Vertex and fragment shader of one PSO, a change of either relinks only this PSO
+struct WatchedShaders {
+    program:Program,
+    files:(String,String),
+    modified:(Option<Time>,Option<Time>),
+}
+
+struct WatchedTexture {
+    file_name:String,
+    texture_id:RgbaTextureID,
+    modified:Option<Time>,
+}
+
+///Development mode, enabled by --dev. Polls texture files, that have been loaded from disk, and the shader
+///sources. Changed textures are uploaded again under the same ID, changed shaders relink their PSO. Errors are
+///printed and the old resource is kept, so a broken file does not stop the render thread
+pub struct HotReload {
+    enabled:bool,
+    last_poll:Time,
+    textures:Vec<WatchedTexture>,
+    shaders:Vec<WatchedShaders>,
+}
+
+impl HotReload {
+    pub fn new(enabled:bool) -> Self {
+        let shaders_directory=shaders_directory();
+
+        let shaders=PROGRAMS.iter().map(|&(program, files)| {
+            let files=(shader_path(shaders_directory, files.0), shader_path(shaders_directory, files.1));
+            let modified=(modified(&files.0), modified(&files.1));
+
+            WatchedShaders {
+                program,
+                files,
+                modified
+            }
+        }).collect();
+
+        if enabled {
+            println!("Hot reload of textures and shaders is on");
+
+            if !Path::new(shaders_directory).is_dir() {
+                println!("Hot reload can not find shaders directory \"{}\" or \"{}\", shaders will not be reloaded",
+                    SHADERS_DIRECTORY, SHADERS_FALLBACK_DIRECTORY);
+            }
+        }
+
+        HotReload {
+            enabled,
+            last_poll:Time::now(),
+            textures:Vec::new(),
+            shaders
+        }
+    }
+
+    pub fn watch_texture(&mut self, file_name:String, texture_id:RgbaTextureID) {
+        if !self.enabled {
+            return;
+        }
+
+        let modified=modified(&file_name);
+
+        self.textures.push(WatchedTexture {
+            file_name,
+            texture_id,
+            modified
+        });
+    }
+
+    ///Deleted textures must not be uploaded again
+    pub fn unwatch_texture(&mut self, texture_id:RgbaTextureID) {
+        self.textures.retain(|texture| texture.texture_id!=texture_id);
+    }
+
+    pub fn poll(&mut self, storage:&mut Storage) {
+        if !self.enabled {
+            return;
+        }
+
+        let now=Time::now();
+
+        match now.duration_since(self.last_poll) {
+            Ok(interval) if interval>=Duration::from_millis(POLL_INTERVAL_MS) => self.last_poll=now,
+            _ => return,
+        }
+
+        self.reload_textures(storage);
+        self.reload_shaders(storage);
+    }
+
+    fn reload_textures(&mut self, storage:&mut Storage) {
+        for texture in self.textures.iter_mut() {
+            let modified=modified(&texture.file_name);
+
+            if modified==texture.modified {
+                continue;
+            }
+
+            texture.modified=modified;
+
+            //The file may be half written, the next change will be loaded again
+            let image_buffer=match ImageFile::decode(&texture.file_name) {
+                Ok(image_buffer) => image_buffer,
+                Err(error) => {
+                    println!("Hot reload error: {}", error);
+                    continue;
+                }
+            };
+
+            match storage.reload_texture(image_buffer, texture.texture_id) {
+                Ok(_) => println!("Reloaded texture \"{}\"", texture.file_name),
+                Err(error) => println!("Hot reload error: {}", error),
+            }
+        }
+    }
+
+    fn reload_shaders(&mut self, storage:&mut Storage) {
+        for shaders in self.shaders.iter_mut() {
+            let modified=(modified(&shaders.files.0), modified(&shaders.files.1));
+
+            if modified==shaders.modified {
+                continue;
+            }
+
+            shaders.modified=modified;
+
+            let (vertex, fragment)=match read_shaders(&shaders.files) {
+                Some(sources) => sources,
+                None => continue,
+            };
+
+            let result=match shaders.program {
+                Program::Object => link_object_pso(&mut storage.gfx_factory, &vertex, &fragment).map(|pso| storage.object_pso=pso),
+                Program::Trace => link_trace_pso(&mut storage.gfx_factory, &vertex, &fragment).map(|pso| storage.trace_pso=pso),
+                Program::TraceInstanced => link_trace_instanced_pso(&mut storage.gfx_factory, &vertex, &fragment).map(|pso| storage.trace_instanced_pso=pso),
+                Program::Text => link_text_pso(&mut storage.gfx_factory, &vertex, &fragment).map(|pso| storage.text_pso=pso),
+                Program::Flat => link_flat_pso(&mut storage.gfx_factory, &vertex, &fragment).map(|pso| storage.flat_pso=pso),
+            };
+
+            match result {
+                Ok(_) => println!("Reloaded {} shaders", shaders.program.name()),
+                Err(error) => println!("Hot reload of {} shaders: {}", shaders.program.name(), error),
+            }
+        }
+    }
+}
+
+fn shaders_directory() -> &'static str {
+    if Path::new(SHADERS_DIRECTORY).is_dir() {
+        SHADERS_DIRECTORY
+    }else{
+        SHADERS_FALLBACK_DIRECTORY
+    }
+}
+
+fn shader_path(directory:&str, shader:&str) -> String {
+    Path::new(directory).join(shader).to_string_lossy().into_owned()
+}
+
+fn modified(file_name:&str) -> Option<Time> {
+    std::fs::metadata(file_name).and_then(|metadata| metadata.modified()).ok()
+}
+
+fn read_shaders(shaders:&(String,String)) -> Option<(Vec<u8>,Vec<u8>)> {
+    match (read_shader(&shaders.0), read_shader(&shaders.1)) {
+        (Some(vertex), Some(fragment)) => Some((vertex, fragment)),
+        _ => None,
+    }
+}
+
+fn read_shader(file_name:&str) -> Option<Vec<u8>> {
+    let mut source=Vec::new();
+
+    match File::open(&file_name).and_then(|mut file| file.read_to_end(&mut source)) {
+        Ok(_) => Some(source),
+        Err(error) => {
+            println!("Hot reload can not read shader \"{}\": {}", file_name, error);
+            None
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use super::Storage;
+use super::Encoder;
+use super::Targets;
+use super::Text;
+
+const MARGIN:f32 = 8.0;
+const PANEL_COLOR:[f32;4] = [0.0, 0.0, 0.0, 0.5];
+const TEXT_COLOR:[f32;4] = [0.95, 0.95, 0.95, 1.0];
+const NO_PATH_COLOR:[f32;4] = [1.0, 0.4, 0.4, 1.0];
+
+///Result of the last algorithm run
+#[derive(Copy, Clone)]
+pub struct PathMetrics {
+    pub length:f32,
+    pub segments:usize,
+}
+
+enum Status {
+    Idle,
+    Running(String),
+    ///Pathfinder and the found path
+    Finished(String, Option<PathMetrics>),
+}
+
+///State of the viewer for the HUD, that the render thread does not keep itself
+pub struct Hud {
+    visible:bool,
+    status:Status,
+}
+
+impl Hud {
+    pub fn new() -> Self {
+        Hud {
+            visible:true,
+            status:Status::Idle,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible=!self.visible;
+    }
+
+    pub fn reset(&mut self) {
+        self.status=Status::Idle;
+    }
+
+    pub fn algorithm_started(&mut self, pathfinder:String) {
+        self.status=Status::Running(pathfinder);
+    }
+
+    pub fn set_path_metrics(&mut self, path:Option<PathMetrics>) {
+        let pathfinder=match self.status {
+            Status::Running(ref pathfinder) | Status::Finished(ref pathfinder, _) => pathfinder.clone(),
+            Status::Idle => String::new(),
+        };
+
+        self.status=Status::Finished(pathfinder, path);
+    }
+
+    ///Draws the panel in the top left corner
+    pub fn draw(&self, text:&mut Text, storage:&Storage, encoder:&mut Encoder, targets:&Targets, screen_size:(u32,u32),
+        cursor:(u32,u32), cursor_a:Option<(u32,u32)>, cursor_b:Option<(u32,u32)>, fps:f32
+    ) {
+        if !self.visible {
+            return;
+        }
+
+        let point=|point:Option<(u32,u32)>| match point {
+            Some((x,z)) => format!("{}, {}", x, z),
+            None => "-".to_string(),
+        };
+
+        let mut lines=vec![
+            (format!("Cursor: {}, {}", cursor.0, cursor.1), TEXT_COLOR),
+            (format!("A: {}   B: {}", point(cursor_a), point(cursor_b)), TEXT_COLOR),
+        ];
+
+        match self.status {
+            Status::Idle => lines.push(("Algorithm: idle".to_string(), TEXT_COLOR)),
+            Status::Running(ref pathfinder) => lines.push((format!("{}: running", pathfinder), TEXT_COLOR)),
+            Status::Finished(ref pathfinder, Some(path)) => {
+                lines.push((format!("{}: finished", pathfinder), TEXT_COLOR));
+                lines.push((format!("Path length: {:.2}", path.length), TEXT_COLOR));
+                lines.push((format!("Segments: {}", path.segments), TEXT_COLOR));
+            },
+            Status::Finished(ref pathfinder, None) => {
+                lines.push((format!("{}: finished", pathfinder), TEXT_COLOR));
+                lines.push(("No path".to_string(), NO_PATH_COLOR));
+            },
+        }
+
+        lines.push((format!("FPS: {:.1}", fps), TEXT_COLOR));
+
+        let width=lines.iter().map(|&(ref line,_)| text.measure(line)).fold(0.0, f32::max);
+        let height=text.line_height*lines.len() as f32;
+
+        let mut vertices=Vec::new();
+        text.layout_rect(MARGIN, MARGIN, width+MARGIN*2.0, height+MARGIN*2.0, PANEL_COLOR, &mut vertices);
+
+        for (i,&(ref line,color)) in lines.iter().enumerate() {
+            text.layout(line, MARGIN*2.0, MARGIN*2.0 + text.line_height*i as f32, color, &mut vertices);
+        }
+
+        text.draw(storage, encoder, targets, screen_size, &vertices);
+    }
+}
@@ -9,6 +9,7 @@ pub use self::render::Encoder;
 pub mod commands;
 pub use self::commands::RenderCommand;
 pub use self::commands::{LoadTexture, LoadMesh, LoadLod, SetSlot};
+pub use self::commands::{DeleteTexture, DeleteMesh, DeleteLod};
 
 pub mod scheduler;
 pub use self::scheduler::Scheduler;
@@ -25,6 +26,9 @@ pub use self::targets::{FinalColorTarget, FinalDepthTarget};
 pub mod storage;
 pub use self::storage::Storage;
 
+pub mod buffer;
+pub use self::buffer::DynamicBuffer;
+
 pub mod camera;
 pub use self::camera::Camera;
 
@@ -33,5 +37,32 @@ pub use self::slots::Slots;
 
 pub mod pipelines;
 
+pub mod capture;
+pub use self::capture::Capture;
+
+pub mod hot_reload;
+pub use self::hot_reload::HotReload;
+
+pub mod text;
+pub use self::text::Text;
+
+pub mod hud;
+pub use self::hud::{Hud, PathMetrics};
+
+pub mod mode;
+pub use self::mode::RenderMode;
+
+pub mod grid;
+pub use self::grid::Grid;
+
+pub mod heatmap;
+pub use self::heatmap::Heatmap;
+
+pub mod terrain;
+pub use self::terrain::Terrain;
+
+pub mod clearance;
+pub use self::clearance::ClearanceOverlay;
+
 pub mod trace;
 pub use self::trace::{Trace, TracePool};
\ No newline at end of file
@@ -0,0 +1,67 @@
+use gfx;
+use gfx_gl;
+
+use cgmath::Matrix4;
+
+use render;
+
+use super::Storage;
+use super::Encoder;
+use super::Targets;
+use super::pipelines::ObjectVertex;
+
+pub const FLOOR_COLOR:[f32;4] = [0.6, 0.6, 0.6, 1.0];
+pub const WALL_COLOR:[f32;4] = [0.85, 0.5, 0.2, 1.0];
+pub const HOLE_COLOR:[f32;4] = [0.15, 0.1, 0.25, 1.0];
+pub const AIR_COLOR:[f32;4] = [0.3, 0.6, 1.0, 0.3];
+pub const OBJECT_COLOR:[f32;4] = [0.9, 0.9, 0.2, 1.0];
+const WIRE_COLOR:[f32;4] = [0.1, 1.0, 0.3, 1.0];
+
+///How the terrain and objects are drawn, cycled with a key to check the geometry
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum RenderMode {
+    Solid,
+    ///Solid with the edges of all triangles over it
+    Wireframe,
+    ///Unlit, every tile type and the objects have their own colour
+    FlatColor,
+}
+
+impl RenderMode {
+    pub fn next(self) -> Self {
+        match self {
+            RenderMode::Solid => RenderMode::Wireframe,
+            RenderMode::Wireframe => RenderMode::FlatColor,
+            RenderMode::FlatColor => RenderMode::Solid,
+        }
+    }
+
+    pub fn is_textured(self) -> bool {
+        self!=RenderMode::FlatColor
+    }
+
+    ///Draws the geometry of the debug modes, textured geometry is drawn by the caller. The color is used in the flat colour mode
+    pub fn draw_debug(self, storage:&Storage, encoder:&mut Encoder, targets:&Targets,
+        vertex_buffer:&gfx::handle::Buffer<gfx_gl::Resources, ObjectVertex>, slice:&gfx::Slice<gfx_gl::Resources>,
+        model_matrix:Matrix4<f32>, color:[f32;4]
+    ) {
+        let (pso, color)=match self {
+            RenderMode::Solid => return,
+            RenderMode::Wireframe => (&storage.flat_pso.wire_pso, WIRE_COLOR),
+            RenderMode::FlatColor => (&storage.flat_pso.pso, color),
+        };
+
+        let data = render::pipelines::FlatPipeline::Data {
+            globals: storage.object_globals.clone(),
+            model_matrix: model_matrix.into(),
+            color: color,
+            vbuf: vertex_buffer.clone(),
+
+            color_target: targets.final_color.clone(),
+            depth_target: targets.final_depth.clone()
+        };
+
+        encoder.draw(slice, pso, &data);
+        storage.count_draw_call();
+    }
+}
@@ -0,0 +1,76 @@
+use gfx;
+use nes::{ErrorInfo,ErrorInfoTrait};
+use gfx_gl;
+
+use gfx::traits::FactoryExt;
+
+use render::Error;
+
+use super::object::{ObjectVertex, ObjectGlobals};
+
+///Object geometry in one colour without a texture, filled for the flat colour mode and as lines for the wireframe
+pub struct FlatPSO {
+    pub pso:gfx::PipelineState<gfx_gl::Resources, FlatPipeline::Meta>,
+    pub wire_pso:gfx::PipelineState<gfx_gl::Resources, FlatPipeline::Meta>,
+}
+
+pub type ColorFormat = gfx::format::Rgba8;
+pub type DepthFormat = gfx::format::DepthStencil;
+
+gfx_defines!{
+    pipeline FlatPipeline {
+        globals: gfx::ConstantBuffer<ObjectGlobals> = "c_globals",
+        model_matrix: gfx::Global<[[f32; 4]; 4]> = "u_model_matrix",
+        color: gfx::Global<[f32; 4]> = "u_color",
+        vbuf: gfx::VertexBuffer<ObjectVertex> = (),
+
+        color_target: gfx::BlendTarget<ColorFormat> = ("Target0", gfx::state::MASK_ALL, gfx::preset::blend::ALPHA),
+        depth_target: gfx::DepthTarget<DepthFormat> = gfx::preset::depth::LESS_EQUAL_WRITE,
+    }
+}
+
+pub const FLAT_SHADERS:(&str, &str) = ("flat_v.glsl", "flat_f.glsl");
+
+pub fn create_flat_pso(gfx_factory: &mut gfx_gl::Factory) -> Result<FlatPSO,Error> {
+    link_flat_pso(
+        gfx_factory,
+        include_bytes!("shaders/flat_v.glsl"),
+        include_bytes!("shaders/flat_f.glsl")
+    )
+}
+
+///Creates the PSO from shader sources, hot reload uses it with sources read from disk
+pub fn link_flat_pso(gfx_factory: &mut gfx_gl::Factory, vertex_shader:&[u8], fragment_shader:&[u8]) -> Result<FlatPSO,Error> {
+    let primitive = gfx::Primitive::TriangleList;
+
+    let shader=try!(gfx_factory.link_program(
+        vertex_shader,
+        fragment_shader,
+    ), Error::CompileShaderError);
+
+    let pso=match gfx_factory.create_pipeline_from_program( &shader, primitive, gfx::state::Rasterizer::new_fill(), FlatPipeline::new() ) {
+        Ok(pso) => pso,
+        Err(error) => return err!(Error::CreatePSOError, Box::new(format!("{}",error))),
+    };
+
+    //Lines lie on the solid surfaces, they are tested against the depth, but do not write it
+    let mut wire_rasterizer = gfx::state::Rasterizer::new_fill();
+    wire_rasterizer.method = gfx::state::RasterMethod::Line(1);
+
+    let wire_init=FlatPipeline::Init {
+        depth_target: gfx::preset::depth::LESS_EQUAL_TEST,
+        ..FlatPipeline::new()
+    };
+
+    let wire_pso=match gfx_factory.create_pipeline_from_program( &shader, primitive, wire_rasterizer, wire_init ) {
+        Ok(pso) => pso,
+        Err(error) => return err!(Error::CreatePSOError, Box::new(format!("{}",error))),
+    };
+
+    let flat_pso=FlatPSO{
+        pso,
+        wire_pso
+    };
+
+    ok!(flat_pso)
+}
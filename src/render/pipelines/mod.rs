@@ -1,6 +1,14 @@
 
 pub mod object;
-pub use self::object::{ObjectVertex, ObjectPipeline, ObjectPSO, create_object_pso};
+pub use self::object::{ObjectVertex, ObjectPipeline, ObjectPSO, create_object_pso, link_object_pso, OBJECT_SHADERS};
 
 pub mod trace;
-pub use self::trace::{TraceVertex, TracePipeline, TracePSO, create_trace_pso};
\ No newline at end of file
+pub use self::trace::{TraceVertex, TracePipeline, TracePSO, create_trace_pso, link_trace_pso, TRACE_SHADERS};
+pub use self::trace::{TraceInstance, TraceInstancedPipeline, TraceInstancedPSO};
+pub use self::trace::{create_trace_instanced_pso, link_trace_instanced_pso, TRACE_INSTANCED_SHADERS};
+
+pub mod text;
+pub use self::text::{TextVertex, TextPipeline, TextPSO, create_text_pso, link_text_pso, TEXT_SHADERS};
+
+pub mod flat;
+pub use self::flat::{FlatPipeline, FlatPSO, create_flat_pso, link_flat_pso, FLAT_SHADERS};
@@ -23,11 +23,16 @@ pub type DepthFormat = gfx::format::DepthStencil;
 gfx_defines!{
     constant ObjectGlobals {
         proj_view_matrix: [[f32; 4]; 4] = "u_proj_view_matrix",
+        light_direction: [f32; 4] = "u_light_direction",
+        light_color: [f32; 4] = "u_light_color",
+        ambient_color: [f32; 4] = "u_ambient_color",
     }
 
     vertex ObjectVertex {
         pos: [f32; 3] = "a_pos",
         uv: [f32; 2] = "a_uv",
+        normal: [f32; 3] = "a_normal",
+        color: [f32; 3] = "a_color",
     }
 
     pipeline ObjectPipeline {
@@ -45,18 +50,41 @@ impl ObjectVertex {
     pub fn new(pos:[i32;3], uv:[i32;2]) -> Self {
         ObjectVertex {
             pos: [pos[0] as f32, pos[2] as f32, pos[1] as f32],
-            uv: [uv[0] as f32, uv[1] as f32]
+            uv: [uv[0] as f32, uv[1] as f32],
+            normal: [0.0, 1.0, 0.0],
+            color: [1.0, 1.0, 1.0]
+        }
+    }
+
+    ///Vertex without a normal yet, the normal is generated from the triangles later
+    pub fn with_uv(pos:[f32;3], uv:[f32;2]) -> Self {
+        ObjectVertex {
+            pos,
+            uv,
+            normal: [0.0, 0.0, 0.0],
+            color: [1.0, 1.0, 1.0]
         }
     }
 }
 
+pub const OBJECT_SHADERS:(&str, &str) = ("object_v.glsl", "object_f.glsl");
+
 pub fn create_object_pso(gfx_factory: &mut gfx_gl::Factory) -> Result<ObjectPSO,Error> {
+    link_object_pso(
+        gfx_factory,
+        include_bytes!("shaders/object_v.glsl"),
+        include_bytes!("shaders/object_f.glsl")
+    )
+}
+
+///Creates the PSO from shader sources, hot reload uses it with sources read from disk
+pub fn link_object_pso(gfx_factory: &mut gfx_gl::Factory, vertex_shader:&[u8], fragment_shader:&[u8]) -> Result<ObjectPSO,Error> {
     let rasterizer = gfx::state::Rasterizer::new_fill();
     let primitive = gfx::Primitive::TriangleList;
 
     let shader=try!(gfx_factory.link_program(
-        include_bytes!("shaders/object_v.glsl"),
-        include_bytes!("shaders/object_f.glsl"),
+        vertex_shader,
+        fragment_shader,
     ), Error::CompileShaderError);
 
     let pso=match gfx_factory.create_pipeline_from_program( &shader, primitive, rasterizer, ObjectPipeline::new() ) {
@@ -64,7 +92,7 @@ pub fn create_object_pso(gfx_factory: &mut gfx_gl::Factory) -> Result<ObjectPSO,
         Err(error) => return err!(Error::CreatePSOError, Box::new(format!("{}",error))),
     };
 
-    let sampler_info=gfx::texture::SamplerInfo::new(gfx::texture::FilterMethod::Bilinear, gfx::texture::WrapMode::Tile);
+    let sampler_info=gfx::texture::SamplerInfo::new(gfx::texture::FilterMethod::Trilinear, gfx::texture::WrapMode::Tile);
     let sampler = gfx_factory.create_sampler(sampler_info);
 
     let object_pso=ObjectPSO{
@@ -0,0 +1,8 @@
+#version 150 core
+
+uniform vec4 u_color;
+out vec4 Target0;
+
+void main() {
+    Target0 = u_color;
+}
@@ -1,10 +1,24 @@
 #version 150 core
+layout(std140) uniform c_globals {
+    mat4 u_proj_view_matrix;
+    vec4 u_light_direction;
+    vec4 u_light_color;
+    vec4 u_ambient_color;
+};
 
 in vec2 v_uv;
+in vec3 v_normal;
+in vec3 v_color;
 out vec4 Target0;
 
 uniform sampler2D t_texture;
 
 void main() {
-    Target0 = texture(t_texture, v_uv);
+    //Winding of the terrain triangles is not consistent, the normal of the visible side is used
+    vec3 normal = normalize(gl_FrontFacing ? v_normal : -v_normal);
+    float diffuse = max(dot(normal, u_light_direction.xyz), 0.0);
+    vec3 light = u_ambient_color.rgb + u_light_color.rgb * diffuse;
+
+    vec4 color = texture(t_texture, v_uv);
+    Target0 = vec4(color.rgb * light * v_color, color.a);
 }
@@ -1,3 +1,6 @@
 layout(std140) uniform c_globals {
     mat4 u_proj_view_matrix;
+    vec4 u_light_direction;
+    vec4 u_light_color;
+    vec4 u_ambient_color;
 };
@@ -1,15 +1,24 @@
 #version 150 core
 layout(std140) uniform c_globals {
     mat4 u_proj_view_matrix;
+    vec4 u_light_direction;
+    vec4 u_light_color;
+    vec4 u_ambient_color;
 };
 
 uniform mat4 u_model_matrix;
 in vec3 a_pos;
 in vec2 a_uv;
+in vec3 a_normal;
+in vec3 a_color;
 
 out vec2 v_uv;
+out vec3 v_normal;
+out vec3 v_color;
 
 void main() {
     v_uv = a_uv;
+    v_normal = mat3(u_model_matrix) * a_normal;
+    v_color = a_color;
     gl_Position = (u_proj_view_matrix * u_model_matrix) * vec4(a_pos, 1.0);
 }
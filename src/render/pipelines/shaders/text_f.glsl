@@ -0,0 +1,11 @@
+#version 150 core
+
+in vec2 v_uv;
+in vec4 v_color;
+out vec4 Target0;
+
+uniform sampler2D t_texture;
+
+void main() {
+    Target0 = vec4(v_color.rgb, v_color.a * texture(t_texture, v_uv).a);
+}
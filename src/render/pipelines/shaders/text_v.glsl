@@ -0,0 +1,19 @@
+#version 150 core
+
+uniform vec2 u_screen_size;
+
+in vec2 a_pos;
+in vec2 a_uv;
+in vec4 a_color;
+
+out vec2 v_uv;
+out vec4 v_color;
+
+void main() {
+    v_uv = a_uv;
+    v_color = a_color;
+
+    //Positions are in pixels from the top left corner
+    vec2 ndc = a_pos / u_screen_size * 2.0 - 1.0;
+    gl_Position = vec4(ndc.x, -ndc.y, 0.0, 1.0);
+}
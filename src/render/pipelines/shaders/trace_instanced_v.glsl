@@ -0,0 +1,20 @@
+#version 150 core
+layout(std140) uniform c_globals {
+    mat4 u_proj_view_matrix;
+};
+
+in vec3 a_pos;
+in vec4 i_transform0;
+in vec4 i_transform1;
+in vec4 i_transform2;
+in vec4 i_transform3;
+in vec4 i_color;
+
+out vec4 v_color;
+
+void main() {
+    mat4 model_matrix = mat4(i_transform0, i_transform1, i_transform2, i_transform3);
+
+    v_color = i_color;
+    gl_Position = (u_proj_view_matrix * model_matrix) * vec4(a_pos, 1.0);
+}
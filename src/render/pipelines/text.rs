@@ -0,0 +1,78 @@
+use gfx;
+use nes::{ErrorInfo,ErrorInfoTrait};
+use gfx_gl;
+
+use gfx::traits::FactoryExt;
+use gfx::Factory;
+
+use render::Error;
+
+pub struct TextPSO {
+    pub pso:gfx::PipelineState<gfx_gl::Resources, TextPipeline::Meta>,
+    pub sampler:gfx::handle::Sampler<gfx_gl::Resources>,
+}
+
+pub type ColorFormat = gfx::format::Rgba8;
+
+gfx_defines!{
+    vertex TextVertex {
+        pos: [f32; 2] = "a_pos",
+        uv: [f32; 2] = "a_uv",
+        color: [f32; 4] = "a_color",
+    }
+
+    pipeline TextPipeline {
+        screen_size: gfx::Global<[f32; 2]> = "u_screen_size",
+        texture: gfx::TextureSampler<[f32; 4]> = "t_texture",
+        vbuf: gfx::VertexBuffer<TextVertex> = (),
+
+        color_target: gfx::BlendTarget<ColorFormat> = ("Target0", gfx::state::MASK_ALL, gfx::preset::blend::ALPHA),
+    }
+}
+
+impl TextVertex {
+    pub fn new(x:f32, y:f32, uv:[f32;2], color:[f32;4]) -> Self {
+        TextVertex {
+            pos:[x,y],
+            uv,
+            color
+        }
+    }
+}
+
+pub const TEXT_SHADERS:(&str, &str) = ("text_v.glsl", "text_f.glsl");
+
+pub fn create_text_pso(gfx_factory: &mut gfx_gl::Factory) -> Result<TextPSO,Error> {
+    link_text_pso(
+        gfx_factory,
+        include_bytes!("shaders/text_v.glsl"),
+        include_bytes!("shaders/text_f.glsl")
+    )
+}
+
+///Creates the PSO from shader sources, hot reload uses it with sources read from disk
+pub fn link_text_pso(gfx_factory: &mut gfx_gl::Factory, vertex_shader:&[u8], fragment_shader:&[u8]) -> Result<TextPSO,Error> {
+    let rasterizer = gfx::state::Rasterizer::new_fill();
+    let primitive = gfx::Primitive::TriangleList;
+
+    let shader=try!(gfx_factory.link_program(
+        vertex_shader,
+        fragment_shader,
+    ), Error::CompileShaderError);
+
+    let pso=match gfx_factory.create_pipeline_from_program( &shader, primitive, rasterizer, TextPipeline::new() ) {
+        Ok(pso) => pso,
+        Err(error) => return err!(Error::CreatePSOError, Box::new(format!("{}",error))),
+    };
+
+    //Glyphs are drawn 1:1 to pixels, the atlas has no mip levels
+    let sampler_info=gfx::texture::SamplerInfo::new(gfx::texture::FilterMethod::Bilinear, gfx::texture::WrapMode::Clamp);
+    let sampler = gfx_factory.create_sampler(sampler_info);
+
+    let text_pso=TextPSO{
+        pso,
+        sampler
+    };
+
+    ok!(text_pso)
+}
@@ -10,7 +10,14 @@ use render::Error;
 //pub type TracePSO=gfx::PipelineState<gfx_gl::Resources, TracePipeline::Meta>;
 
 pub struct TracePSO {
-    pub pso:gfx::PipelineState<gfx_gl::Resources, TracePipeline::Meta>
+    pub pso:gfx::PipelineState<gfx_gl::Resources, TracePipeline::Meta>,
+    ///Same shaders for line lists, the grid is drawn with it
+    pub line_pso:gfx::PipelineState<gfx_gl::Resources, TracePipeline::Meta>,
+}
+
+///Draws all traces with one call per shared mesh, every instance has its own transform and color
+pub struct TraceInstancedPSO {
+    pub pso:gfx::PipelineState<gfx_gl::Resources, TraceInstancedPipeline::Meta>
 }
 
 pub type ColorFormat = gfx::format::Rgba8;
@@ -37,6 +44,24 @@ gfx_defines!{
         color_target: gfx::BlendTarget<ColorFormat> = ("Target0", gfx::state::MASK_ALL, gfx::preset::blend::ALPHA),
         depth_target: gfx::DepthTarget<DepthFormat> = gfx::preset::depth::LESS_EQUAL_WRITE,
     }
+
+    ///Columns of the model matrix
+    vertex TraceInstance {
+        transform0: [f32; 4] = "i_transform0",
+        transform1: [f32; 4] = "i_transform1",
+        transform2: [f32; 4] = "i_transform2",
+        transform3: [f32; 4] = "i_transform3",
+        color: [f32; 4] = "i_color",
+    }
+
+    pipeline TraceInstancedPipeline {
+        globals: gfx::ConstantBuffer<TraceGlobals> = "c_globals",
+        vbuf: gfx::VertexBuffer<TraceVertex> = (),
+        instances: gfx::InstanceBuffer<TraceInstance> = (),
+
+        color_target: gfx::BlendTarget<ColorFormat> = ("Target0", gfx::state::MASK_ALL, gfx::preset::blend::ALPHA),
+        depth_target: gfx::DepthTarget<DepthFormat> = gfx::preset::depth::LESS_EQUAL_WRITE,
+    }
 }
 
 impl TraceVertex {
@@ -45,13 +70,37 @@ impl TraceVertex {
     }
 }
 
+impl TraceInstance {
+    pub fn new(model_matrix:[[f32;4];4], color:[f32;4]) -> Self {
+        TraceInstance {
+            transform0:model_matrix[0],
+            transform1:model_matrix[1],
+            transform2:model_matrix[2],
+            transform3:model_matrix[3],
+            color
+        }
+    }
+}
+
+pub const TRACE_SHADERS:(&str, &str) = ("trace_v.glsl", "trace_f.glsl");
+pub const TRACE_INSTANCED_SHADERS:(&str, &str) = ("trace_instanced_v.glsl", "trace_instanced_f.glsl");
+
 pub fn create_trace_pso(gfx_factory: &mut gfx_gl::Factory) -> Result<TracePSO,Error> {
+    link_trace_pso(
+        gfx_factory,
+        include_bytes!("shaders/trace_v.glsl"),
+        include_bytes!("shaders/trace_f.glsl")
+    )
+}
+
+///Creates the PSO from shader sources, hot reload uses it with sources read from disk
+pub fn link_trace_pso(gfx_factory: &mut gfx_gl::Factory, vertex_shader:&[u8], fragment_shader:&[u8]) -> Result<TracePSO,Error> {
     let rasterizer = gfx::state::Rasterizer::new_fill();
     let primitive = gfx::Primitive::TriangleList;
 
     let shader=try!(gfx_factory.link_program(
-        include_bytes!("shaders/trace_v.glsl"),
-        include_bytes!("shaders/trace_f.glsl"),
+        vertex_shader,
+        fragment_shader,
     ), Error::CompileShaderError);
 
     let pso=match gfx_factory.create_pipeline_from_program( &shader, primitive, rasterizer, TracePipeline::new() ) {
@@ -59,9 +108,43 @@ pub fn create_trace_pso(gfx_factory: &mut gfx_gl::Factory) -> Result<TracePSO,Er
         Err(error) => return err!(Error::CreatePSOError, Box::new(format!("{}",error))),
     };
 
+    let line_pso=match gfx_factory.create_pipeline_from_program( &shader, gfx::Primitive::LineList, rasterizer, TracePipeline::new() ) {
+        Ok(pso) => pso,
+        Err(error) => return err!(Error::CreatePSOError, Box::new(format!("{}",error))),
+    };
+
     let trace_pso=TracePSO{
-        pso
+        pso,
+        line_pso
     };
 
     ok!(trace_pso)
-}
\ No newline at end of file
+}
+pub fn create_trace_instanced_pso(gfx_factory: &mut gfx_gl::Factory) -> Result<TraceInstancedPSO,Error> {
+    link_trace_instanced_pso(
+        gfx_factory,
+        include_bytes!("shaders/trace_instanced_v.glsl"),
+        include_bytes!("shaders/trace_instanced_f.glsl")
+    )
+}
+
+pub fn link_trace_instanced_pso(gfx_factory: &mut gfx_gl::Factory, vertex_shader:&[u8], fragment_shader:&[u8]) -> Result<TraceInstancedPSO,Error> {
+    let rasterizer = gfx::state::Rasterizer::new_fill();
+    let primitive = gfx::Primitive::TriangleList;
+
+    let shader=try!(gfx_factory.link_program(
+        vertex_shader,
+        fragment_shader,
+    ), Error::CompileShaderError);
+
+    let pso=match gfx_factory.create_pipeline_from_program( &shader, primitive, rasterizer, TraceInstancedPipeline::new() ) {
+        Ok(pso) => pso,
+        Err(error) => return err!(Error::CreatePSOError, Box::new(format!("{}",error))),
+    };
+
+    let trace_instanced_pso=TraceInstancedPSO{
+        pso
+    };
+
+    ok!(trace_instanced_pso)
+}
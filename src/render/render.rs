@@ -15,6 +15,7 @@ use glutin::EventsLoop;
 
 use std::thread;
 use std::thread::JoinHandle;
+use std::collections::HashSet;
 
 use supervisor;
 use supervisor::SupervisorSender;
@@ -37,17 +38,39 @@ use super::Storage;
 use super::Slots;
 use super::RenderCommand;
 use super::{LoadTexture, LoadMesh, LoadLod, SetSlot};
+use super::{DeleteTexture, DeleteMesh, DeleteLod};
 use super::{Trace,TracePool};
+use super::Capture;
+use super::Terrain;
+use super::ClearanceOverlay;
+use super::HotReload;
+use super::Scheduler;
+use super::{Text, Hud};
+use super::Heatmap;
+use super::Grid;
+use super::RenderMode;
+use super::mode::AIR_COLOR;
 
 pub type RenderSender = reactor::Sender<ThreadSource,RenderCommand>;
 pub type RenderReceiver = reactor::Receiver<ThreadSource,RenderCommand>;
 
 pub type Encoder = gfx::Encoder<gfx_gl::Resources, gfx_gl::CommandBuffer>;
 
-pub use process::{Map,Tile};
+pub use process::{Map,Tile,FlowField};
 
 const CLEAR_COLOR: [f32; 4] = [0.1, 0.2, 0.3, 1.0];
 
+const ARROW_COLOR: [f32; 4] = [0.9, 0.9, 0.9, 0.8];
+
+///Direction towards the sun, from the side and above, so wall faces get different brightness
+const LIGHT_DIRECTION: [f32; 3] = [0.4, 0.8, 0.3];
+const LIGHT_COLOR: [f32; 4] = [0.75, 0.75, 0.7, 1.0];
+const AMBIENT_COLOR: [f32; 4] = [0.35, 0.35, 0.4, 1.0];
+
+const CAPTURE_DIRECTORY: &str = "capture";
+
+const STATS_LOG_INTERVAL_S: u64 = 5;
+
 
 pub struct Render {
     render_receiver:RenderReceiver,
@@ -63,7 +86,6 @@ pub struct Render {
     gfx_device: gfx_gl::Device,
     encoder: Encoder,
     //pub pso: gfx::PipelineState<gfx_gl::Resources, pipe::Meta>,
-    //pso_wire: gfx::PipelineState<gfx_gl::Resources, pipe::Meta>,
     storage: Storage,
     slots: Slots,
     //font: rusttype::Font<'static>,
@@ -72,12 +94,37 @@ pub struct Render {
     resources_loaded:bool,
     camera:CommonCamera,
     map:Option<Map>,
+    terrain:Terrain,
     cursor_pos:(u32,u32),
     cursor_a:Option<(u32,u32)>,
     cursor_b:Option<(u32,u32)>,
     traces:TracePool,
-    tiles:Vec<(u32,u32)>,
+    heatmap:Heatmap,
+    grid:Grid,
     cursor_tile:Option<(u32,u32)>,
+    clearance:ClearanceOverlay,
+    show_clearance:bool,
+    flow_field:Option<FlowField>,
+    show_flow_field:bool,
+    render_mode:RenderMode,
+    ambient_occlusion:bool,
+
+    screenshots:Vec<String>,
+    capture_algorithm:bool,
+    capture_runs:u32,
+    capture_run:Option<(String,u32)>,
+    ///The algorithm has finished, the run is closed after its next frame
+    capture_run_finished:bool,
+
+    reported_resource_errors:HashSet<String>,
+    hot_reload:HotReload,
+
+    text:Text,
+    hud:Hud,
+
+    scheduler:Scheduler,
+    log_stats:bool,
+    last_stats_log:Time,
 }
 
 impl Render{
@@ -210,6 +257,8 @@ impl Render{
         };
 
         let storage=Storage::new(gfx_factory.clone())?;
+        let text=Text::new(&mut gfx_factory)?;
+        let grid=Grid::new(&mut gfx_factory);
 
         let mut encoder: gfx::Encoder<_, _> = gfx_factory.create_command_buffer().into();
 
@@ -230,12 +279,36 @@ impl Render{
             resources_loaded:false,
             camera,
             map:None,
+            terrain:Terrain::new(),
             cursor_pos:(0,0),
             cursor_a:None,
             cursor_b:None,
             traces:TracePool::new(),
-            tiles:Vec::new(),
-            cursor_tile:None
+            heatmap:Heatmap::new(),
+            grid,
+            cursor_tile:None,
+            clearance:ClearanceOverlay::new(),
+            show_clearance:false,
+            flow_field:None,
+            show_flow_field:true,
+            render_mode:RenderMode::Solid,
+            ambient_occlusion:true,
+
+            screenshots:Vec::new(),
+            capture_algorithm:std::env::args().any(|arg| arg=="--capture"),
+            capture_runs:0,
+            capture_run:None,
+            capture_run_finished:false,
+
+            reported_resource_errors:HashSet::new(),
+            hot_reload:HotReload::new(std::env::args().any(|arg| arg=="--dev")),
+
+            text,
+            hud:Hud::new(),
+
+            scheduler:Scheduler::new(Self::get_fps()),
+            log_stats:std::env::args().any(|arg| arg=="--stats"),
+            last_stats_log:Time::now(),
         };
 
         ok!(render)
@@ -251,45 +324,126 @@ impl Render{
         ok!()
     }
 
+    ///Target FPS is given by --fps=N
+    fn get_fps() -> u32 {
+        let fps=std::env::args()
+            .filter(|arg| arg.starts_with("--fps="))
+            .filter_map(|arg| arg["--fps=".len()..].parse::<u32>().ok())
+            .filter(|&fps| fps>0)
+            .next();
+
+        fps.unwrap_or(DEFAULT_FPS)
+    }
+
     fn lifecycle(&mut self) -> Result<(),Error> {
+        println!("Target FPS: {}", self.scheduler.get_fps());
+
         loop {
-            self.render()?;
+            self.scheduler.begin_frame();
 
             if self.handle_render_commands()? {
                 return ok!();
             }
+
+            self.scheduler.commands_handled();
+
+            self.render()?;
+
+            let draw_calls=self.storage.take_draw_calls();
+            self.scheduler.frame_rendered(draw_calls);
+            self.log_stats();
+
+            match self.scheduler.make_plan() {
+                Some(sleep_i) => thread::sleep(sleep_i),
+                None => {},
+            }
+        }
+    }
+
+    ///With --stats the statistics are printed periodically
+    fn log_stats(&mut self) {
+        if !self.log_stats {
+            return;
+        }
+
+        let now=Time::now();
+
+        match now.duration_since(self.last_stats_log) {
+            Ok(interval) if interval>=Duration::new(STATS_LOG_INTERVAL_S,0) => {
+                println!("Render stats: {}", self.scheduler.get_stats());
+                self.last_stats_log=now;
+            },
+            _ => {},
         }
     }
 
+    ///Handles pending commands without blocking, until the queue is empty or the time planned for commands is over
     fn handle_render_commands(&mut self) -> Result<bool,Error> {
         loop {
-            match try_recv_block!(self.render_receiver) {
+            if self.scheduler.commands_time_is_over() {
+                return ok!(false);
+            }
+
+            let command=match try_recv!(self.render_receiver) {
+                Some(command) => command,
+                None => return ok!(false),
+            };
+
+            match command {
                 RenderCommand::ThreadCrash(thread) => return err!(Error::ThreadCrash, thread),
-                RenderCommand::Tick => return ok!(false),
                 RenderCommand::Shutdown => return ok!(true),
 
                 RenderCommand::ResizeWindow(width, height) =>
                     self.resize_window(width,height)?,
 
-                RenderCommand::LoadTexture(load_texture) =>
-                    self.load_texture(load_texture)?,
-                RenderCommand::LoadMesh(load_mesh) =>
-                    self.load_mesh(load_mesh)?,
-                RenderCommand::LoadLod(load_lod) =>
-                    self.load_lod(load_lod)?,
+                RenderCommand::LoadTexture(load_texture) => {
+                    let result=self.load_texture(load_texture);
+                    self.log_resource_error(result)?
+                },
+                RenderCommand::LoadMesh(load_mesh) => {
+                    let result=self.load_mesh(load_mesh);
+                    self.log_resource_error(result)?
+                },
+                RenderCommand::LoadLod(load_lod) => {
+                    let result=self.load_lod(load_lod);
+                    self.log_resource_error(result)?
+                },
+                RenderCommand::DeleteTexture(delete_texture) => {
+                    let result=self.delete_texture(delete_texture);
+                    self.log_resource_error(result)?
+                },
+                RenderCommand::WatchTexture(file_name, texture_id) =>
+                    self.hot_reload.watch_texture(file_name, texture_id),
+                RenderCommand::DeleteMesh(delete_mesh) => {
+                    let result=self.delete_mesh(delete_mesh);
+                    self.log_resource_error(result)?
+                },
+                RenderCommand::DeleteLod(delete_lod) => {
+                    let result=self.delete_lod(delete_lod);
+                    self.log_resource_error(result)?
+                },
                 RenderCommand::SetSlot(set_slot) =>
                     self.slots.set_slot(set_slot),
-                RenderCommand::CreateMap =>
-                    self.map=Some(Map::new()),
+                RenderCommand::CreateMap => {
+                    self.map=Some(Map::new());
+                    self.terrain=Terrain::new();
+                    self.clearance=ClearanceOverlay::new();
+                },
                 RenderCommand::LoadTile(x,z,tile) => {
                     match self.map {
-                        Some(ref mut map) => map.tiles[x][z]=tile,
+                        Some(ref mut map) => {
+                            let side=map.set_tile(x,z,tile);
+                            self.terrain.invalidate(x,z);
+                            self.clearance.invalidate(x,z,side);
+                        },
                         None => {}
                     }
                 },
 
                 RenderCommand::MoveCursor(x,z) =>
                     self.cursor_pos=(x,z),
+                RenderCommand::HoverTile(hover) =>
+                    self.grid.set_hover(hover),
                 RenderCommand::SetCursorA(cursor_a) =>
                     self.cursor_a=cursor_a,
                 RenderCommand::SetCursorB(cursor_b) =>
@@ -306,16 +460,73 @@ impl Render{
                     self.traces.delete(trace_id),
                 RenderCommand::SetTraceColor(trace_id,color) =>
                     self.traces.set_color(trace_id,color),
+                RenderCommand::ScaleTraceThickness(factor) => {
+                    let thickness=self.traces.scale_thickness(factor);
+                    println!("Trace thickness: {:.3}", thickness);
+                },
                 RenderCommand::AddTile(x,z,mc) => {
-                    self.tiles.push((x,z));
+                    self.heatmap.visit(x,z,mc);
                     if mc {
                         self.cursor_tile = Some((x, z));
                     }
                 },
+                RenderCommand::StartWalk =>
+                    self.heatmap.start_walk(),
                 RenderCommand::ClearTiles => {
-                    self.tiles.clear();
+                    self.heatmap.clear_last_run();
+                    self.cursor_tile=None;
+                },
+                RenderCommand::ToggleGrid => {
+                    let visible=self.grid.toggle();
+                    println!("Grid: {}", if visible {"on"} else {"off"});
+                },
+                RenderCommand::ToggleHeatmapScope => {
+                    let scope=self.heatmap.toggle_scope();
+                    println!("Heatmap: {:?}", scope);
+                },
+                RenderCommand::Reset => {
+                    self.cursor_a=None;
+                    self.cursor_b=None;
+                    self.heatmap.reset();
                     self.cursor_tile=None;
+                    self.flow_field=None;
+                    self.hud.reset();
                 },
+                RenderCommand::ToggleClearance =>
+                    self.show_clearance=!self.show_clearance,
+                RenderCommand::SetFlowField(flow_field) =>
+                    self.flow_field=Some(flow_field),
+                RenderCommand::ToggleFlowField =>
+                    self.show_flow_field=!self.show_flow_field,
+                RenderCommand::NextRenderMode => {
+                    self.render_mode=self.render_mode.next();
+                    println!("Render mode: {:?}", self.render_mode);
+                },
+                RenderCommand::ToggleAmbientOcclusion => {
+                    self.ambient_occlusion=!self.ambient_occlusion;
+                    self.terrain.invalidate_all();
+                    println!("Ambient occlusion: {}", if self.ambient_occlusion {"on"} else {"off"});
+                },
+
+                RenderCommand::Screenshot(file_name) =>
+                    self.screenshots.push(file_name),
+                RenderCommand::PrintStats =>
+                    println!("Render stats: {}", self.scheduler.get_stats()),
+                RenderCommand::ToggleHud =>
+                    self.hud.toggle(),
+                RenderCommand::ToggleCapture => {
+                    self.capture_algorithm=!self.capture_algorithm;
+                    println!("Capture of algorithm runs: {}", if self.capture_algorithm {"on"} else {"off"});
+                },
+                RenderCommand::AlgorithmStarted(pathfinder) => {
+                    self.heatmap.clear_last_run();
+                    self.hud.algorithm_started(pathfinder);
+                    self.algorithm_started()
+                },
+                RenderCommand::AlgorithmFinished =>
+                    self.capture_run_finished=true,
+                RenderCommand::SetPathMetrics(path) =>
+                    self.hud.set_path_metrics(path),
 
                 _ => unreachable!()
             }
@@ -324,22 +535,137 @@ impl Render{
 
     fn render(&mut self) -> Result<(),Error> {
         self.gfx_device.cleanup();
-        self.encoder.clear(&self.targets.final_color, CLEAR_COLOR);
-        self.encoder.clear_depth(&self.targets.final_depth, 1.0);
+        self.hot_reload.poll(&mut self.storage);
 
         if self.resources_loaded {
-            self.render_map()?;
+            let result=match self.map {
+                Some(ref map) => self.terrain.update(map, &self.slots, &mut self.storage, self.ambient_occlusion),
+                None => ok!(),
+            };
+
+            self.log_resource_error(result)?;
+        }
+
+        if self.show_clearance {
+            match self.map {
+                Some(ref map) => self.clearance.update(map, &mut self.storage.gfx_factory),
+                None => {},
+            }
         }
 
+        let targets=self.targets.clone();
+        self.render_frame(&targets)?;
+
         //self.encoder.draw(&slice, &self.storage.terrain_pso, &data);
         self.encoder.flush(&mut self.gfx_device);
 
+        let capture_files=self.take_capture_files();
+
+        if !capture_files.is_empty() {
+            let (width,height)=self.window.get_size();
+            let capture=Capture::read(&mut self.gfx_device, width, height);
+
+            for file_name in capture_files.iter() {
+                match capture.save(file_name.as_str()) {
+                    Ok(_) => {},
+                    Err(error) => println!("Capture error: {}", error),
+                }
+            }
+        }
+
         self.window.swap_buffers()?;
 
         ok!()
     }
 
-    fn render_map(&mut self) -> Result<(),Error> {
+    fn render_frame(&mut self, targets:&Targets) -> Result<(),Error> {
+        self.encoder.clear(&targets.final_color, CLEAR_COLOR);
+        self.encoder.clear_depth(&targets.final_depth, 1.0);
+
+        if self.resources_loaded {
+            let result=self.render_map(targets);
+            self.log_resource_error(result)?;
+        }
+
+        self.hud.draw(&mut self.text, &self.storage, &mut self.encoder, targets, self.window.get_size(),
+            self.cursor_pos, self.cursor_a, self.cursor_b, self.scheduler.get_stats().fps
+        );
+
+        self.heatmap.draw_legend(&mut self.text, &self.storage, &mut self.encoder, targets, self.window.get_size());
+
+        if self.resources_loaded {
+            match self.camera.get_render_camera()? {
+                Some(camera) => {
+                    let proj_view_matrix=camera.projection_matrix * camera.camera_matrix;
+                    self.grid.draw_labels(&mut self.text, &self.storage, &mut self.encoder, targets, self.window.get_size(), &proj_view_matrix);
+                },
+                None => {},
+            }
+        }
+
+        ok!()
+    }
+
+    ///Missing, stale or replaced resources are logged instead of crashing the render thread. Errors of
+    ///draws repeat every frame, so every error is printed only the first time
+    fn log_resource_error(&mut self, result:Result<(),Error>) -> Result<(),Error> {
+        match result {
+            Ok(_) => ok!(),
+            Err(error) => match error {
+                Error::NoTexture(..) | Error::NoMesh(..) | Error::NoLod(..) |
+                Error::TextureReplaced(..) | Error::MeshReplaced(..) | Error::LodReplaced(..) => {
+                    let message=format!("{}", error);
+
+                    if !self.reported_resource_errors.contains(&message) {
+                        println!("Render resource error: {}", message);
+                        self.reported_resource_errors.insert(message);
+                    }
+
+                    ok!()
+                },
+                _ => Err(error),
+            }
+        }
+    }
+
+    ///File names, that the current frame should be saved to: requested screenshots and the next frame of the running capture.
+    ///A finished run still gets this frame, so a run, that started and finished between two frames, is not empty
+    fn take_capture_files(&mut self) -> Vec<String> {
+        let mut capture_files:Vec<String>=self.screenshots.drain(..).collect();
+
+        match self.capture_run {
+            Some((ref directory, ref mut frame)) => {
+                capture_files.push(format!("{}/frame{:05}.png", directory, frame));
+                *frame+=1;
+            },
+            None => {}
+        }
+
+        if self.capture_run_finished {
+            self.capture_run=None;
+            self.capture_run_finished=false;
+        }
+
+        capture_files
+    }
+
+    fn algorithm_started(&mut self) {
+        self.capture_run_finished=false;
+
+        if !self.capture_algorithm {
+            return;
+        }
+
+        self.capture_runs+=1;
+        let directory=format!("{}/run{:03}", CAPTURE_DIRECTORY, self.capture_runs);
+
+        match std::fs::create_dir_all(&directory) {
+            Ok(_) => self.capture_run=Some((directory,0)),
+            Err(error) => println!("Can not create capture directory \"{}\": {}", directory, error),
+        }
+    }
+
+    fn render_map(&mut self, targets:&Targets) -> Result<(),Error> {
         //use storage::mesh::MeshID;
         //use gfx::traits::FactoryExt;
         //use gfx::Factory;
@@ -347,13 +673,19 @@ impl Render{
         //use cgmath::SquareMatrix;
         //use gfx::texture::SamplerInfo;
 
+        use cgmath::{Vector3,InnerSpace};
+
         let camera=self.camera.get_render_camera()?.unwrap();
-        let proj_view_matrix=camera.perspective_matrix * camera.camera_matrix;
+        let proj_view_matrix=camera.projection_matrix * camera.camera_matrix;
+        let light_direction=Vector3::from(LIGHT_DIRECTION).normalize();
 
         self.encoder.update_constant_buffer(
             &self.storage.object_globals,
             &super::pipelines::object::ObjectGlobals {
-                proj_view_matrix: proj_view_matrix.into()
+                proj_view_matrix: proj_view_matrix.into(),
+                light_direction: light_direction.extend(0.0).into(),
+                light_color: LIGHT_COLOR,
+                ambient_color: AMBIENT_COLOR,
             },
         );
 
@@ -364,68 +696,28 @@ impl Render{
             },
         );
 
-        match self.map {
-            Some(ref map) => {
-                for z in 0..MAP_SIZE {
-                    for x in 0..MAP_SIZE {
-                        match map.tiles[x][z] {
-                            Tile::Air => {},
-                            Tile::Floor(index) => {
-                                let mesh_id=self.slots.floor_mesh;
-                                let texture_id=self.slots.terrain_textures[index];
-
-                                self.storage.terrain_meshes.get(mesh_id)?.draw(
-                                    &self.storage, &mut self.encoder, &self.targets,
-                                    x as u32,z as u32,texture_id
-                                )?;
-                            }
-                            Tile::Wall(index) => {
-                                let r=if x<MAP_SIZE-1 && map.tiles[x+1][z].is_wall() {0}else{1<<0};
-                                let l=if x>0 && map.tiles[x-1][z].is_wall() {0}else{1<<1};
-                                let f=if z<MAP_SIZE-1 && map.tiles[x][z+1].is_wall() {0}else{1<<2};
-                                let b=if z>0 && map.tiles[x][z-1].is_wall() {0}else{1<<3};
-
-                                let mask=r | l | f | b;
-
-                                let mesh_id=self.slots.wall_meshes[mask];
-                                let texture_id=self.slots.terrain_textures[index];
-
-                                self.storage.terrain_meshes.get(mesh_id)?.draw(
-                                    &self.storage, &mut self.encoder, &self.targets,
-                                    x as u32,z as u32,texture_id
-                                )?;
-                            },
-                            Tile::Hole(index) => {
-                                let r=if x<MAP_SIZE-1 && map.tiles[x+1][z].is_hole() {0}else{1<<0};
-                                let l=if x>0 && map.tiles[x-1][z].is_hole() {0}else{1<<1};
-                                let f=if z<MAP_SIZE-1 && map.tiles[x][z+1].is_hole() {0}else{1<<2};
-                                let b=if z>0 && map.tiles[x][z-1].is_hole() {0}else{1<<3};
-
-                                let mask=r | l | f | b;
-
-                                let mesh_id=self.slots.hole_meshes[mask];
-                                let texture_id=self.slots.terrain_textures[index];
-
-                                self.storage.terrain_meshes.get(mesh_id)?.draw(
-                                    &self.storage, &mut self.encoder, &self.targets,
-                                    x as u32,z as u32,texture_id
-                                )?;
-                            },
-                        }
-                    }
-                }
-            },
-            None => {},
+        if self.map.is_some() {
+            self.terrain.draw(&self.storage, &mut self.encoder, targets, self.render_mode)?;
         }
 
+        self.grid.draw(&self.storage, &mut self.encoder, targets)?;
+
         //CursorA
         match self.cursor_a {
             Some((x,z)) => {
                 let mesh_id=self.slots.cursor_a;
                 self.storage.object_meshes.get(mesh_id)?.draw(
-                    &self.storage, &mut self.encoder, &self.targets,
+                    &self.storage, &mut self.encoder, targets, &camera, self.render_mode,
                     x, 0.05, z,
                 )?;
+
+                //Unit stands in the center of its footprint
+                for &mesh_id in self.slots.unit.iter() {
+                    self.storage.object_meshes.get(mesh_id)?.draw(
+                        &self.storage, &mut self.encoder, targets, &camera, self.render_mode,
+                        x+AGENT_SIZE/2, 0.0, z+AGENT_SIZE/2,
+                    )?;
+                }
             },
             None => {},
         }
@@ -435,28 +727,22 @@ impl Render{
             Some((x,z)) => {
                 let mesh_id=self.slots.cursor_b;
                 self.storage.object_meshes.get(mesh_id)?.draw(
-                    &self.storage, &mut self.encoder, &self.targets,
+                    &self.storage, &mut self.encoder, targets, &camera, self.render_mode,
                     x, 0.05, z,
                 )?;
             },
             None => {},
         }
 
-        //Tiles
-        for &(x,z) in self.tiles.iter() {
-            let mesh_id=self.slots.tile;
-            self.storage.object_meshes.get(mesh_id)?.draw(
-                &self.storage, &mut self.encoder, &self.targets,
-                x, 0.025,z
-            )?;
-        }
+        //Visited tiles
+        self.heatmap.draw(&self.storage, &mut self.encoder, targets, &self.slots)?;
 
         //CursorTile
         match self.cursor_tile {
             Some((x,z)) => {
                 let mesh_id=self.slots.cursor;
                 self.storage.object_meshes.get(mesh_id)?.draw(
-                    &self.storage, &mut self.encoder, &self.targets,
+                    &self.storage, &mut self.encoder, targets, &camera, self.render_mode,
                     x, 0.04, z,
                 )?;
             },
@@ -466,11 +752,78 @@ impl Render{
         //Cursor
         let mesh_id=self.slots.cursor;
         self.storage.object_meshes.get(mesh_id)?.draw(
-            &self.storage, &mut self.encoder, &self.targets,
+            &self.storage, &mut self.encoder, targets, &camera, self.render_mode,
             self.cursor_pos.0, 0.1,self.cursor_pos.1,
         )?;
 
-        self.traces.draw(&self.storage, &mut self.encoder, &self.targets)?;
+        if self.render_mode==RenderMode::FlatColor {
+            self.render_air(targets)?;
+        }
+
+        if self.show_clearance {
+            self.clearance.draw(&self.storage, &mut self.encoder, targets);
+        }
+
+        if self.show_flow_field {
+            self.render_flow_field(targets)?;
+        }
+
+        self.traces.draw(&self.storage, &mut self.encoder, targets, &self.slots)?;
+
+        ok!()
+    }
+
+    ///Air has no geometry, in the flat colour mode it is marked by a quad, so it can be told apart from holes
+    fn render_air(&mut self, targets:&Targets) -> Result<(),Error> {
+        use cgmath::{Matrix4,Vector3};
+
+        let map=match self.map {
+            Some(ref map) => map,
+            None => return ok!(),
+        };
+
+        let mesh=self.storage.trace_meshes.get(self.slots.overlay_quad)?;
+
+        for z in 0..MAP_SIZE {
+            for x in 0..MAP_SIZE {
+                match map.tiles[x][z] {
+                    Tile::Air => {},
+                    _ => continue,
+                }
+
+                let model_matrix=Matrix4::from_translation(Vector3::new(x as f32, 0.0, z as f32));
+
+                mesh.draw_matrix(&self.storage, &mut self.encoder, targets, model_matrix, AIR_COLOR)?;
+            }
+        }
+
+        ok!()
+    }
+
+    fn render_flow_field(&mut self, targets:&Targets) -> Result<(),Error> {
+        use cgmath::{Matrix4,Vector3,Rad};
+
+        let flow_field=match self.flow_field {
+            Some(ref flow_field) => flow_field,
+            None => return ok!(),
+        };
+
+        let mesh=self.storage.trace_meshes.get(self.slots.arrow)?;
+
+        for z in 0..MAP_SIZE as u32 {
+            for x in 0..MAP_SIZE as u32 {
+                let (dx,dz)=match flow_field.sample(x,z) {
+                    Some(direction) => direction,
+                    None => continue,
+                };
+
+                //Same placement as traces: the center of the agent footprint
+                let pos_matrix=Matrix4::from_translation(Vector3::new(x as f32 + 1.0, 0.05, z as f32 + 1.0));
+                let rot_matrix=Matrix4::from_angle_y(Rad((dx as f32).atan2(dz as f32)));
+
+                mesh.draw_matrix(&self.storage, &mut self.encoder, targets, pos_matrix*rot_matrix, ARROW_COLOR)?;
+            }
+        }
 
         ok!()
     }
@@ -507,10 +860,45 @@ impl Render{
         use super::storage::LodStorage;
 
         match load_lod {
-            LoadLod::Object(vertex_buffer, lod_id) =>
-                self.storage.load_lod(vertex_buffer, lod_id),
-            LoadLod::Trace(vertex_buffer, lod_id) =>
-                self.storage.load_lod(vertex_buffer, lod_id),
+            LoadLod::Object(vertices, indices, lod_id) =>
+                self.storage.load_lod(vertices, indices, lod_id),
+            LoadLod::Trace(vertices, indices, lod_id) =>
+                self.storage.load_lod(vertices, indices, lod_id),
+        }
+    }
+
+    fn delete_texture(&mut self, delete_texture:DeleteTexture) -> Result<(),Error> {
+        use super::storage::TextureStorage;
+
+        match delete_texture {
+            DeleteTexture::RGBA(texture_id) => {
+                self.hot_reload.unwatch_texture(texture_id);
+                self.storage.delete_texture(texture_id)
+            }
+        }
+    }
+
+    fn delete_mesh(&mut self, delete_mesh:DeleteMesh) -> Result<(),Error> {
+        use super::storage::MeshStorage;
+
+        match delete_mesh {
+            DeleteMesh::Object(mesh_id) =>
+                self.storage.delete_mesh(mesh_id),
+            DeleteMesh::Terrain(mesh_id) =>
+                self.storage.delete_mesh(mesh_id),
+            DeleteMesh::Trace(mesh_id) =>
+                self.storage.delete_mesh(mesh_id),
+        }
+    }
+
+    fn delete_lod(&mut self, delete_lod:DeleteLod) -> Result<(),Error> {
+        use super::storage::LodStorage;
+
+        match delete_lod {
+            DeleteLod::Object(lod_id) =>
+                self.storage.delete_lod(lod_id),
+            DeleteLod::Trace(lod_id) =>
+                self.storage.delete_lod(lod_id),
         }
     }
 
@@ -1,19 +1,57 @@
+use std;
 
 use types::*;
 
+use std::collections::VecDeque;
+
+///Number of the last frames, that statistics are collected over
+const STATS_FRAMES:usize = 120;
+
+struct FrameRecord {
+    ///From the begin of the previous frame to the begin of this one, including the sleep
+    interval:Duration,
+    frame_i:Duration,
+    commands_i:Duration,
+    rendering_i:Duration,
+    draw_calls:usize,
+}
+
+///Rolling statistics of the last frames, times are in milliseconds
+pub struct FrameStats {
+    pub frames:usize,
+    pub fps:f32,
+    pub frame_avg:f32,
+    pub frame_max:f32,
+    pub commands_avg:f32,
+    pub rendering_avg:f32,
+    pub draw_calls_avg:f32,
+    pub draw_calls_max:usize,
+}
+
+impl std::fmt::Display for FrameStats{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:.1} fps, frame {:.2} ms (max {:.2} ms), commands {:.2} ms, rendering {:.2} ms, {:.0} draw calls (max {}) over {} frames",
+            self.fps, self.frame_avg, self.frame_max, self.commands_avg, self.rendering_avg,
+            self.draw_calls_avg, self.draw_calls_max, self.frames
+        )
+    }
+}
+
+///Paces the render loop to the target FPS and records the time of every frame phase
 pub struct Scheduler {
     normal_fi:Duration,
     normal_fps:u32,
 
     pub frame_begin:Time,
-    pub poll_window_events_end:Time,
     pub handle_render_commands_end:Time,
-    pub handle_storage_commands_end:Time,
     pub rendering_end:Time,
     pub frame_end:Time,
 
-    pub plan_storage_commands_handling_i:Duration,
-    pub plan_fi:Duration
+    ///Commands are handled until this part of the frame interval is spent, the rest is left for rendering
+    plan_commands_handling_i:Duration,
+
+    previous_frame_begin:Option<Time>,
+    history:VecDeque<FrameRecord>,
 }
 
 impl Scheduler {
@@ -25,30 +63,109 @@ impl Scheduler {
             normal_fps:fps,
 
             frame_begin:Time::now(),
-            poll_window_events_end:Time::now(),
             handle_render_commands_end:Time::now(),
-            handle_storage_commands_end:Time::now(),
             rendering_end:Time::now(),
             frame_end:Time::now(),
 
-            plan_storage_commands_handling_i:Duration::new(0,10_000_000),
-            plan_fi:Duration::new(0,20_000_000)
+            plan_commands_handling_i:normal_fi/2,
+
+            previous_frame_begin:None,
+            history:VecDeque::with_capacity(STATS_FRAMES),
         }
     }
 
-    pub fn make_plan(&mut self) -> Option<Duration>{
-        let frame_i=self.frame_end.duration_since(self.frame_begin).unwrap();
-        let rendering_i=self.rendering_end.duration_since(self.handle_storage_commands_end).unwrap();
+    pub fn get_fps(&self) -> u32 {
+        self.normal_fps
+    }
 
-        self.plan_storage_commands_handling_i=Duration::new(0,10_000_000);
-        self.plan_fi=Duration::new(0,20_000_000);
+    pub fn begin_frame(&mut self) {
+        self.previous_frame_begin=Some(self.frame_begin);
+        self.frame_begin=Time::now();
+    }
+
+    ///Commands, that do not fit into the plan, are left for the next frame
+    pub fn commands_time_is_over(&self) -> bool {
+        elapsed(self.frame_begin, Time::now()) > self.plan_commands_handling_i
+    }
 
-        if frame_i > self.normal_fi {
+    pub fn commands_handled(&mut self) {
+        self.handle_render_commands_end=Time::now();
+    }
+
+    pub fn frame_rendered(&mut self, draw_calls:usize) {
+        self.rendering_end=Time::now();
+        self.frame_end=self.rendering_end;
+
+        let frame_i=elapsed(self.frame_begin, self.frame_end);
+
+        let record=FrameRecord {
+            interval:match self.previous_frame_begin {
+                Some(previous_frame_begin) => elapsed(previous_frame_begin, self.frame_begin),
+                None => frame_i,
+            },
+            frame_i,
+            commands_i:elapsed(self.frame_begin, self.handle_render_commands_end),
+            rendering_i:elapsed(self.handle_render_commands_end, self.rendering_end),
+            draw_calls,
+        };
+
+        if self.history.len()==STATS_FRAMES {
+            self.history.pop_front();
+        }
+
+        self.history.push_back(record);
+    }
+
+    ///Time to sleep until the next frame, None if the frame has taken longer than planned
+    pub fn make_plan(&mut self) -> Option<Duration>{
+        let frame_i=elapsed(self.frame_begin, self.frame_end);
+
+        if frame_i >= self.normal_fi {
             None
         }else{
             Some(self.normal_fi - frame_i)
         }
     }
+
+    pub fn get_stats(&self) -> FrameStats {
+        let frames=self.history.len();
+
+        if frames==0 {
+            return FrameStats {
+                frames,
+                fps:0.0,
+                frame_avg:0.0,
+                frame_max:0.0,
+                commands_avg:0.0,
+                rendering_avg:0.0,
+                draw_calls_avg:0.0,
+                draw_calls_max:0,
+            };
+        }
+
+        let sum=|value:&Fn(&FrameRecord) -> f32| self.history.iter().map(|record| value(record)).sum::<f32>();
+        let interval=sum(&|record| ms(record.interval))/frames as f32;
+
+        FrameStats {
+            frames,
+            fps:if interval>0.0 {1000.0/interval} else {0.0},
+            frame_avg:sum(&|record| ms(record.frame_i))/frames as f32,
+            frame_max:self.history.iter().map(|record| ms(record.frame_i)).fold(0.0, f32::max),
+            commands_avg:sum(&|record| ms(record.commands_i))/frames as f32,
+            rendering_avg:sum(&|record| ms(record.rendering_i))/frames as f32,
+            draw_calls_avg:sum(&|record| record.draw_calls as f32)/frames as f32,
+            draw_calls_max:self.history.iter().map(|record| record.draw_calls).max().unwrap_or(0),
+        }
+    }
+}
+
+///The system clock may go back, such intervals are counted as zero
+fn elapsed(from:Time, to:Time) -> Duration {
+    to.duration_since(from).unwrap_or(Duration::new(0,0))
+}
+
+fn ms(duration:Duration) -> f32 {
+    duration.as_secs() as f32*1000.0 + duration.subsec_nanos() as f32/1_000_000.0
 }
 
 /*
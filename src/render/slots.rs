@@ -1,7 +1,6 @@
 
-use types::{RgbaTextureID,TerrainMeshID,ObjectMeshID};
+use types::{RgbaTextureID,TerrainMeshID,ObjectMeshID,TraceMeshID};
 
-use object_pool::growable::ID;
 use storage::{TextureID,MeshID};
 
 use render::SetSlot;
@@ -17,12 +16,17 @@ pub struct Slots {
     pub terrain_textures:Vec<RgbaTextureID>,
     pub floor_mesh:TerrainMeshID,
     pub wall_meshes:Vec<TerrainMeshID>,
-    pub hole_meshes:Vec<TerrainMeshID>
+    pub hole_meshes:Vec<TerrainMeshID>,
+    pub overlay_quad:TraceMeshID,
+    pub arrow:TraceMeshID,
+    ///Box and arrowhead of unit size, traces are drawn as their instances
+    pub trace_body:TraceMeshID,
+    pub trace_head:TraceMeshID,
+    pub unit:Vec<ObjectMeshID>,
 }
 
 impl Slots {
     pub fn new() -> Self {
-        let terrain_textures=vec![RgbaTextureID::zeroed();5];
         let wall_meshes=vec![TerrainMeshID::zeroed();16];
         let hole_meshes=vec![TerrainMeshID::zeroed();16];
 
@@ -31,10 +35,15 @@ impl Slots {
             cursor_a:ObjectMeshID::zeroed(),
             cursor_b:ObjectMeshID::zeroed(),
             tile:ObjectMeshID::zeroed(),
-            terrain_textures,
-            floor_mesh:TerrainMeshID::new(ID::zeroed()),
+            terrain_textures:Vec::new(),
+            floor_mesh:TerrainMeshID::zeroed(),
             wall_meshes,
-            hole_meshes
+            hole_meshes,
+            overlay_quad:TraceMeshID::zeroed(),
+            arrow:TraceMeshID::zeroed(),
+            trace_body:TraceMeshID::zeroed(),
+            trace_head:TraceMeshID::zeroed(),
+            unit:Vec::new(),
         };
 
         slots
@@ -50,14 +59,30 @@ impl Slots {
                 self.cursor_b=mesh_id,
             SetSlot::Tile(mesh_id) =>
                 self.tile=mesh_id,
-            SetSlot::TerrainTexture(index, texture_id) =>
-                self.terrain_textures[index]=texture_id,
+            SetSlot::TerrainTexture(index, texture_id) => {
+                //The number of terrain textures is set by the asset manifest
+                while self.terrain_textures.len()<=index {
+                    self.terrain_textures.push(RgbaTextureID::zeroed());
+                }
+
+                self.terrain_textures[index]=texture_id;
+            },
             SetSlot::FloorMesh(mesh_id) =>
                 self.floor_mesh=mesh_id,
             SetSlot::WallMesh(index, mesh_id) =>
                 self.wall_meshes[index]=mesh_id,
             SetSlot::HoleMesh(index, mesh_id) =>
                 self.hole_meshes[index]=mesh_id,
+            SetSlot::OverlayQuad(mesh_id) =>
+                self.overlay_quad=mesh_id,
+            SetSlot::Arrow(mesh_id) =>
+                self.arrow=mesh_id,
+            SetSlot::TraceBody(mesh_id) =>
+                self.trace_body=mesh_id,
+            SetSlot::TraceHead(mesh_id) =>
+                self.trace_head=mesh_id,
+            SetSlot::Unit(meshes) =>
+                self.unit=meshes,
         }
     }
 }
\ No newline at end of file
@@ -3,6 +3,8 @@ use gfx_gl;
 
 use types::*;
 
+use std::collections::HashMap;
+
 use gfx::Factory;
 use gfx::traits::FactoryExt;
 
@@ -14,23 +16,120 @@ use render::Error;
 pub trait Lod:Sized {
     type V;
 
-    fn new(buffer:Vec<Self::V>, gfx_factory: &mut gfx_gl::Factory) -> Result<Self,Error>;
+    fn new(vertices:Vec<Self::V>, indices:Vec<u32>, gfx_factory: &mut gfx_gl::Factory) -> Result<Self,Error>;
+}
+
+///Bit pattern of the vertex, equal vertices are merged, when a lod is indexed
+pub trait VertexKey:Copy {
+    fn key(&self) -> Vec<u32>;
+}
+
+impl VertexKey for ObjectVertex {
+    fn key(&self) -> Vec<u32> {
+        self.pos.iter().chain(self.uv.iter()).chain(self.normal.iter()).chain(self.color.iter()).map(|c| c.to_bits()).collect()
+    }
+}
+
+impl VertexKey for TraceVertex {
+    fn key(&self) -> Vec<u32> {
+        self.pos.iter().map(|c| c.to_bits()).collect()
+    }
+}
+
+///Turns a triangle list with duplicated vertices into unique vertices and indices
+pub fn index_vertices<V:VertexKey>(buffer:&[V]) -> (Vec<V>, Vec<u32>) {
+    let mut vertices=Vec::with_capacity(buffer.len());
+    let mut indices=Vec::with_capacity(buffer.len());
+    let mut known:HashMap<Vec<u32>,u32>=HashMap::new();
+
+    for vertex in buffer.iter() {
+        let index=*known.entry(vertex.key()).or_insert_with(|| {
+            vertices.push(*vertex);
+            (vertices.len()-1) as u32
+        });
+
+        indices.push(index);
+    }
+
+    (vertices, indices)
+}
+
+///Normal of the triangle, the side follows the winding: a, b and c are counterclockwise, when seen from it
+fn triangle_normal(a:[f32;3], b:[f32;3], c:[f32;3]) -> [f32;3] {
+    let u=[b[0]-a[0], b[1]-a[1], b[2]-a[2]];
+    let v=[c[0]-a[0], c[1]-a[1], c[2]-a[2]];
+
+    [u[1]*v[2]-u[2]*v[1], u[2]*v[0]-u[0]*v[2], u[0]*v[1]-u[1]*v[0]]
+}
+
+fn normalize(n:[f32;3]) -> [f32;3] {
+    let length=(n[0]*n[0] + n[1]*n[1] + n[2]*n[2]).sqrt();
+
+    if length>0.0 {
+        [n[0]/length, n[1]/length, n[2]/length]
+    }else{
+        [0.0, 1.0, 0.0]
+    }
+}
+
+///Sets the normal of every triangle of the list to its vertices, edges stay sharp
+pub fn face_normals(buffer:&mut [ObjectVertex]) {
+    for triangle in buffer.chunks_mut(3) {
+        if triangle.len()<3 {
+            break;
+        }
+
+        let normal=normalize(triangle_normal(triangle[0].pos, triangle[1].pos, triangle[2].pos));
+
+        for vertex in triangle.iter_mut() {
+            vertex.normal=normal;
+        }
+    }
+}
+
+///Averages normals of the triangles around every shared vertex, larger triangles weigh more
+pub fn smooth_normals(vertices:&mut [ObjectVertex], indices:&[u32]) {
+    let mut normals=vec![[0.0f32;3]; vertices.len()];
+
+    for triangle in indices.chunks(3) {
+        if triangle.len()<3 {
+            break;
+        }
+
+        let (a,b,c)=(triangle[0] as usize, triangle[1] as usize, triangle[2] as usize);
+        let normal=triangle_normal(vertices[a].pos, vertices[b].pos, vertices[c].pos);
+
+        for &i in [a,b,c].iter() {
+            for k in 0..3 {
+                normals[i][k]+=normal[k];
+            }
+        }
+    }
+
+    for (vertex,normal) in vertices.iter_mut().zip(normals.into_iter()) {
+        vertex.normal=normalize(normal);
+    }
 }
 
 pub struct ObjectLod {
     pub vertex_buffer:gfx::handle::Buffer<gfx_gl::Resources, ObjectVertex>,
-    pub slice:gfx::Slice<gfx_gl::Resources>
+    pub slice:gfx::Slice<gfx_gl::Resources>,
+    ///CPU copy of the geometry, terrain chunks are baked from it
+    pub vertices:Vec<ObjectVertex>,
+    pub indices:Vec<u32>,
 }
 
 impl Lod for ObjectLod {
     type V=ObjectVertex;
 
-    fn new(buffer:Vec<Self::V>, gfx_factory: &mut gfx_gl::Factory) -> Result<Self,Error> {
-        let (vertex_buffer, slice) = gfx_factory.create_vertex_buffer_with_slice(&buffer[..], ());
+    fn new(vertices:Vec<Self::V>, indices:Vec<u32>, gfx_factory: &mut gfx_gl::Factory) -> Result<Self,Error> {
+        let (vertex_buffer, slice) = gfx_factory.create_vertex_buffer_with_slice(&vertices[..], &indices[..]);
 
         let lod=ObjectLod {
             vertex_buffer,
-            slice
+            slice,
+            vertices,
+            indices
         };
 
         ok!(lod)
@@ -47,8 +146,8 @@ pub struct TraceLod {
 impl Lod for TraceLod {
     type V=TraceVertex;
 
-    fn new(buffer:Vec<Self::V>, gfx_factory: &mut gfx_gl::Factory) -> Result<Self,Error> {
-        let (vertex_buffer, slice) = gfx_factory.create_vertex_buffer_with_slice(&buffer[..], ());
+    fn new(vertices:Vec<Self::V>, indices:Vec<u32>, gfx_factory: &mut gfx_gl::Factory) -> Result<Self,Error> {
+        let (vertex_buffer, slice) = gfx_factory.create_vertex_buffer_with_slice(&vertices[..], &indices[..]);
 
         let lod=TraceLod {
             vertex_buffer,
@@ -57,4 +156,4 @@ impl Lod for TraceLod {
 
         ok!(lod)
     }
-}
\ No newline at end of file
+}
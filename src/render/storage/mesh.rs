@@ -1,21 +1,29 @@
 
+use std;
 use nes::{ErrorInfo,ErrorInfoTrait};
 use gfx;
 use gfx_gl;
 
 use types::*;
-use cgmath::Rad;
 
 use gfx::Factory;
 use gfx::traits::FactoryExt;
 
 use cgmath::Matrix4;
 use cgmath::Vector3;
+use cgmath::MetricSpace;
+
+use location::Pos3D;
 
 use render;
 use render::Targets;
 use render::Error;
 use render::Encoder;
+use render::Camera;
+use render::RenderMode;
+use render::mode::OBJECT_COLOR;
+use render::DynamicBuffer;
+use render::pipelines::TraceInstance;
 
 use super::Storage;
 
@@ -23,38 +31,63 @@ pub trait Mesh {
 }
 
 pub struct ObjectMesh {
-    pub lod:ObjectLodID,
+    ///LODs with distances sorted from the most detailed one. A LOD is drawn, while the camera
+    ///is closer than its distance, the last one is drawn at any distance
+    pub lods:Vec<(ObjectLodID,f32)>,
     pub texture:RgbaTextureID,
 }
 
 impl ObjectMesh {
     pub fn new(lod:ObjectLodID, texture:RgbaTextureID) -> Self {
         ObjectMesh {
-            lod,
+            lods:vec![(lod, std::f32::INFINITY)],
+            texture
+        }
+    }
+
+    pub fn with_lods(mut lods:Vec<(ObjectLodID,f32)>, texture:RgbaTextureID) -> Self {
+        lods.sort_by(|a,b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        ObjectMesh {
+            lods,
             texture
         }
     }
 
-    pub fn draw(&self, storage:&Storage, encoder:&mut Encoder, targets:&Targets,
+    pub fn select_lod(&self, distance:f32) -> ObjectLodID {
+        match self.lods.iter().find(|&&(_,max_distance)| distance<max_distance) {
+            Some(&(lod_id,_)) => lod_id,
+            None => self.lods[self.lods.len()-1].0,
+        }
+    }
+
+    pub fn draw(&self, storage:&Storage, encoder:&mut Encoder, targets:&Targets, camera:&Camera, mode:RenderMode,
         x:u32, y:f32, z:u32
     ) -> Result<(),Error> {
-        let lod_id=self.lod;
+        let position=Pos3D::new(x as f32, y, z as f32);
+        let lod_id=self.select_lod(camera.camera_position.distance(position));
         let lod=storage.object_lods.get(lod_id)?;
-        let texture=storage.textures_rgba.get(self.texture)?;
 
         let model_matrix=Matrix4::from_translation(Vector3::new(x as f32,y, z as f32));
 
-        let data = render::pipelines::ObjectPipeline::Data {
-            globals: storage.object_globals.clone(),
-            model_matrix: model_matrix.into(),
-            texture: (texture.view.clone(), storage.object_pso.sampler.clone()),
-            vbuf: lod.vertex_buffer.clone(),
+        if mode.is_textured() {
+            let texture=storage.textures_rgba.get(self.texture)?;
 
-            color_target: targets.final_color.clone(),
-            depth_target: targets.final_depth.clone()
-        };
+            let data = render::pipelines::ObjectPipeline::Data {
+                globals: storage.object_globals.clone(),
+                model_matrix: model_matrix.into(),
+                texture: (texture.view.clone(), storage.object_pso.sampler.clone()),
+                vbuf: lod.vertex_buffer.clone(),
 
-        encoder.draw(&lod.slice, &storage.object_pso.pso, &data);
+                color_target: targets.final_color.clone(),
+                depth_target: targets.final_depth.clone()
+            };
+
+            encoder.draw(&lod.slice, &storage.object_pso.pso, &data);
+            storage.count_draw_call();
+        }
+
+        mode.draw_debug(storage, encoder, targets, &lod.vertex_buffer, &lod.slice, model_matrix, OBJECT_COLOR);
 
         ok!()
     }
@@ -74,30 +107,6 @@ impl TerrainMesh {
             lod
         }
     }
-
-    pub fn draw(&self, storage:&Storage, encoder:&mut Encoder, targets:&Targets,
-        x:u32, z:u32, texture_id:RgbaTextureID
-    ) -> Result<(),Error> {
-        let lod_id=self.lod;
-        let lod=storage.object_lods.get(lod_id)?;
-        let texture=storage.textures_rgba.get(texture_id)?;
-
-        let model_matrix=Matrix4::from_translation(Vector3::new(x as f32,0.0, z as f32));
-
-        let data = render::pipelines::ObjectPipeline::Data {
-            globals: storage.object_globals.clone(),
-            model_matrix: model_matrix.into(),
-            texture: (texture.view.clone(), storage.object_pso.sampler.clone()),
-            vbuf: lod.vertex_buffer.clone(),
-
-            color_target: targets.final_color.clone(),
-            depth_target: targets.final_depth.clone()
-        };
-
-        encoder.draw(&lod.slice, &storage.object_pso.pso, &data);
-
-        ok!()
-    }
 }
 
 impl Mesh for TerrainMesh{}
@@ -114,16 +123,40 @@ impl TraceMesh {
         }
     }
 
-    pub fn draw(&self, storage:&Storage, encoder:&mut Encoder, targets:&Targets,
-                x:u32, z:u32, angle:f32, color:[f32;4]
+    ///Draws all instances with one call, the instances are uploaded into the buffer of the caller
+    pub fn draw_instanced(&self, storage:&Storage, encoder:&mut Encoder, targets:&Targets,
+                instances:&[TraceInstance], instance_buffer:&mut DynamicBuffer<TraceInstance>
     ) -> Result<(),Error> {
-        let lod_id=self.lod;
-        let lod=storage.trace_lods.get(lod_id)?;
+        if instances.is_empty() {
+            return ok!();
+        }
+
+        let lod=storage.trace_lods.get(self.lod)?;
+        let instance_buffer=instance_buffer.upload(&mut storage.gfx_factory.clone(), encoder, instances)?;
 
-        let pos_matrix=Matrix4::from_translation(Vector3::new(x as f32,0.6, z as f32));
-        let rot_matrix=Matrix4::from_angle_y(Rad(angle));
+        let mut slice=lod.slice.clone();
+        slice.instances=Some((instances.len() as u32, 0));
 
-        let model_matrix=pos_matrix*rot_matrix;
+        let data = render::pipelines::TraceInstancedPipeline::Data {
+            globals: storage.trace_globals.clone(),
+            vbuf: lod.vertex_buffer.clone(),
+            instances: instance_buffer,
+
+            color_target: targets.final_color.clone(),
+            depth_target: targets.final_depth.clone()
+        };
+
+        encoder.draw(&slice, &storage.trace_instanced_pso.pso, &data);
+        storage.count_draw_call();
+
+        ok!()
+    }
+
+    pub fn draw_matrix(&self, storage:&Storage, encoder:&mut Encoder, targets:&Targets,
+                model_matrix:Matrix4<f32>, color:[f32;4]
+    ) -> Result<(),Error> {
+        let lod_id=self.lod;
+        let lod=storage.trace_lods.get(lod_id)?;
 
         let data = render::pipelines::TracePipeline::Data {
             globals: storage.trace_globals.clone(),
@@ -136,6 +169,7 @@ impl TraceMesh {
         };
 
         encoder.draw(&lod.slice, &storage.trace_pso.pso, &data);
+        storage.count_draw_call();
 
         ok!()
     }
@@ -11,6 +11,7 @@ pub use self::mesh::{ObjectMesh, TerrainMesh, TraceMesh};
 
 pub mod lod;
 pub use self::lod::{ObjectLod, TraceLod};
+pub use self::lod::{VertexKey, index_vertices, face_normals, smooth_normals};
 
 pub use render::pipelines::ObjectVertex;
 pub use render::pipelines::TraceVertex;
\ No newline at end of file
@@ -6,8 +6,9 @@ use render;
 use types::*;
 
 use std::marker::PhantomData;
+use std::cell::Cell;
 
-use object_pool::growable::Pool;
+use storage::HandleMap;
 
 use gfx::traits::FactoryExt;
 use gfx_gl::Factory;
@@ -17,6 +18,9 @@ use storage::{TextureID, MeshID, LodID};
 use render::Error;
 use render::pipelines::{ObjectPSO, create_object_pso};
 use render::pipelines::{TracePSO, create_trace_pso};
+use render::pipelines::{TraceInstancedPSO, create_trace_instanced_pso};
+use render::pipelines::{TextPSO, create_text_pso};
+use render::pipelines::{FlatPSO, create_flat_pso};
 
 use super::ObjectVertex;
 use super::TraceVertex;
@@ -27,6 +31,8 @@ use super::lod::*;
 
 pub trait TextureStorage<ID:TextureID,IB> {
     fn load_texture(&mut self, image_buffer:IB, texture_id:ID) -> Result<(), Error>;
+    ///Replaces the texture of a live handle, used by the hot reload
+    fn reload_texture(&mut self, image_buffer:IB, texture_id:ID) -> Result<(), Error>;
     fn delete_texture(&mut self, texture_id:ID) -> Result<(), Error>;
 }
 
@@ -36,7 +42,7 @@ pub trait MeshStorage<ID:MeshID,M:Mesh> {
 }
 
 pub trait LodStorage<ID:LodID,L:Lod,V> {
-    fn load_lod(&mut self, vertex_buffer:Vec<V>, lod_id:ID) -> Result<(), Error>;
+    fn load_lod(&mut self, vertices:Vec<V>, indices:Vec<u32>, lod_id:ID) -> Result<(), Error>;
     fn delete_lod(&mut self, lod_id:ID) -> Result<(), Error>;
 }
 
@@ -44,6 +50,9 @@ pub struct Storage {
     pub gfx_factory: Factory,
     pub object_pso: ObjectPSO,
     pub trace_pso: TracePSO,
+    pub trace_instanced_pso: TraceInstancedPSO,
+    pub text_pso: TextPSO,
+    pub flat_pso: FlatPSO,
     //pub fake_texture = u32;
 
     pub textures_rgba:InnerTextureStorage<RgbaTextureID, RgbaImage, RgbaTexture>,
@@ -57,18 +66,27 @@ pub struct Storage {
 
     pub object_globals: gfx::handle::Buffer<gfx_gl::Resources, render::pipelines::object::ObjectGlobals>,
     pub trace_globals: gfx::handle::Buffer<gfx_gl::Resources, render::pipelines::trace::TraceGlobals>,
+
+    ///Draw calls of the current frame, meshes are drawn through a shared reference
+    draw_calls:Cell<usize>,
 }
 
 impl Storage {
     pub fn new(mut gfx_factory: Factory) -> Result<Self,Error> {
         let object_pso=create_object_pso(&mut gfx_factory)?;
         let trace_pso=create_trace_pso(&mut gfx_factory)?;
+        let trace_instanced_pso=create_trace_instanced_pso(&mut gfx_factory)?;
+        let text_pso=create_text_pso(&mut gfx_factory)?;
+        let flat_pso=create_flat_pso(&mut gfx_factory)?;
         //let fake_texture = load_texture_raw(&mut gfx_factory, Size2{w: 2, h: 2}, &[0; 4]);
 
         let storage=Storage {
             gfx_factory:gfx_factory.clone(),
             object_pso,
             trace_pso,
+            trace_instanced_pso,
+            text_pso,
+            flat_pso,
             //fake_texture
 
             textures_rgba:InnerTextureStorage::new(&gfx_factory),
@@ -83,15 +101,26 @@ impl Storage {
 
             object_globals:gfx_factory.create_constant_buffer(1),
             trace_globals:gfx_factory.create_constant_buffer(1),
+
+            draw_calls:Cell::new(0),
         };
 
         ok!(storage)
     }
+
+    pub fn count_draw_call(&self) {
+        self.draw_calls.set(self.draw_calls.get()+1);
+    }
+
+    ///Returns the number of draw calls since the last call
+    pub fn take_draw_calls(&self) -> usize {
+        self.draw_calls.replace(0)
+    }
 }
 
 pub struct InnerTextureStorage<ID:TextureID,IB,T:Texture<IB=IB>> {
     gfx_factory: Factory,
-    pool:Pool<T, T>,
+    pool:HandleMap<T>,
     _phantom_data:PhantomData<(ID,IB)>
 }
 
@@ -99,7 +128,7 @@ impl<ID:TextureID,IB,T:Texture<IB=IB>> InnerTextureStorage<ID,IB,T> {
     fn new(gfx_factory: &Factory) -> Self {
         InnerTextureStorage {
             gfx_factory:gfx_factory.clone(),
-            pool:Pool::new(),
+            pool:HandleMap::new(),
             _phantom_data:PhantomData
         }
     }
@@ -107,8 +136,20 @@ impl<ID:TextureID,IB,T:Texture<IB=IB>> InnerTextureStorage<ID,IB,T> {
     fn load(&mut self, image_buffer:IB, texture_id:ID) -> Result<(), Error> {
         let texture=T::new(image_buffer, &mut self.gfx_factory)?;
 
-        let id=self.pool.insert(texture);
-        assert_eq!(id, texture_id.get_id());
+        //The new texture is stored anyway, the old one is dropped with its GPU resources
+        match self.pool.insert(texture_id.get_id(), texture) {
+            Some(_) => err!(Error::TextureReplaced, texture_id.get_id()),
+            None => ok!(),
+        }
+    }
+
+    fn reload(&mut self, image_buffer:IB, texture_id:ID) -> Result<(), Error> {
+        if self.pool.get(texture_id.get_id()).is_none() {
+            return err!(Error::NoTexture, texture_id.get_id());
+        }
+
+        let texture=T::new(image_buffer, &mut self.gfx_factory)?;
+        self.pool.insert(texture_id.get_id(), texture);
 
         ok!()
     }
@@ -116,50 +157,58 @@ impl<ID:TextureID,IB,T:Texture<IB=IB>> InnerTextureStorage<ID,IB,T> {
     pub fn get(&self, texture_id:ID) -> Result<&T, Error> {
         match self.pool.get(texture_id.get_id()) {
             Some(texture) => ok!(texture),
-            None => err!(Error::NoTexture)
+            None => err!(Error::NoTexture, texture_id.get_id())
         }
     }
 
+    ///Drops the resource, GPU buffers are freed on the next cleanup of the device
     fn delete(&mut self, texture_id:ID) -> Result<(), Error> {
-        ok!()
+        match self.pool.remove(texture_id.get_id()) {
+            Some(_) => ok!(),
+            None => err!(Error::NoTexture, texture_id.get_id())
+        }
     }
 }
 
 pub struct InnerMeshStorage<ID:MeshID,M:Mesh> {
-    pool:Pool<M, M>,
+    pool:HandleMap<M>,
     _phantom_data:PhantomData<(ID)>
 }
 
 impl<ID:MeshID,M:Mesh> InnerMeshStorage<ID,M> {
     fn new() -> Self {
         InnerMeshStorage {
-            pool:Pool::new(),
+            pool:HandleMap::new(),
             _phantom_data:PhantomData
         }
     }
 
     fn load(&mut self, mesh:M, mesh_id:ID) -> Result<(), Error> {
-        let id=self.pool.insert(mesh);
-        assert_eq!(id, mesh_id.get_id());
-
-        ok!()
+        match self.pool.insert(mesh_id.get_id(), mesh) {
+            Some(_) => err!(Error::MeshReplaced, mesh_id.get_id()),
+            None => ok!(),
+        }
     }
 
     pub fn get(&self, mesh_id:ID) -> Result<&M, Error> {
         match self.pool.get(mesh_id.get_id()) {
             Some(mesh) => ok!(mesh),
-            None => err!(Error::NoMesh)
+            None => err!(Error::NoMesh, mesh_id.get_id())
         }
     }
 
+    ///Drops the resource, GPU buffers are freed on the next cleanup of the device
     fn delete(&mut self, mesh_id:ID) -> Result<(), Error> {
-        ok!()
+        match self.pool.remove(mesh_id.get_id()) {
+            Some(_) => ok!(),
+            None => err!(Error::NoMesh, mesh_id.get_id())
+        }
     }
 }
 
 pub struct InnerLodStorage<ID:LodID,V,L:Lod<V=V>> {
     gfx_factory: Factory,
-    pool:Pool<L, L>,
+    pool:HandleMap<L>,
     _phantom_data:PhantomData<(ID,V)>
 }
 
@@ -167,29 +216,33 @@ impl<ID:LodID,V,L:Lod<V=V>> InnerLodStorage<ID,V,L> {
     fn new(gfx_factory: &Factory) -> Self {
         InnerLodStorage {
             gfx_factory:gfx_factory.clone(),
-            pool:Pool::new(),
+            pool:HandleMap::new(),
             _phantom_data:PhantomData
         }
     }
 
-    fn load(&mut self, vertex_buffer:Vec<V>, lod_id:ID) -> Result<(), Error> {
-        let lod=L::new(vertex_buffer, &mut self.gfx_factory)?;
-        
-        let id=self.pool.insert(lod);
-        assert_eq!(id, lod_id.get_id());
+    fn load(&mut self, vertices:Vec<V>, indices:Vec<u32>, lod_id:ID) -> Result<(), Error> {
+        let lod=L::new(vertices, indices, &mut self.gfx_factory)?;
 
-        ok!()
+        match self.pool.insert(lod_id.get_id(), lod) {
+            Some(_) => err!(Error::LodReplaced, lod_id.get_id()),
+            None => ok!(),
+        }
     }
 
     pub fn get(&self, lod_id:ID) -> Result<&L, Error> {
         match self.pool.get(lod_id.get_id()) {
             Some(lod) => ok!(lod),
-            None => err!(Error::NoLod)
+            None => err!(Error::NoLod, lod_id.get_id())
         }
     }
 
+    ///Drops the resource, GPU buffers are freed on the next cleanup of the device
     fn delete(&mut self, lod_id:ID) -> Result<(), Error> {
-        ok!()
+        match self.pool.remove(lod_id.get_id()) {
+            Some(_) => ok!(),
+            None => err!(Error::NoLod, lod_id.get_id())
+        }
     }
 }
 
@@ -199,6 +252,10 @@ impl TextureStorage<RgbaTextureID, RgbaImage> for Storage {
         self.textures_rgba.load(image_buffer, texture_id)
     }
 
+    fn reload_texture(&mut self, image_buffer:RgbaImage, texture_id:RgbaTextureID) -> Result<(), Error> {
+        self.textures_rgba.reload(image_buffer, texture_id)
+    }
+
     fn delete_texture(&mut self, texture_id:RgbaTextureID) -> Result<(), Error> {
         self.textures_rgba.delete(texture_id)
     }
@@ -235,8 +292,8 @@ impl MeshStorage<TraceMeshID, TraceMesh> for Storage {
 }
 
 impl LodStorage<ObjectLodID, ObjectLod, ObjectVertex> for Storage {
-    fn load_lod(&mut self, vertex_buffer:Vec<ObjectVertex>, lod_id:ObjectLodID) -> Result<(),Error> {
-        self.object_lods.load(vertex_buffer, lod_id)
+    fn load_lod(&mut self, vertices:Vec<ObjectVertex>, indices:Vec<u32>, lod_id:ObjectLodID) -> Result<(),Error> {
+        self.object_lods.load(vertices, indices, lod_id)
     }
 
     fn delete_lod(&mut self, lod_id:ObjectLodID) -> Result<(),Error> {
@@ -245,8 +302,8 @@ impl LodStorage<ObjectLodID, ObjectLod, ObjectVertex> for Storage {
 }
 
 impl LodStorage<TraceLodID, TraceLod, TraceVertex> for Storage {
-    fn load_lod(&mut self, vertex_buffer:Vec<TraceVertex>, lod_id:TraceLodID) -> Result<(),Error> {
-        self.trace_lods.load(vertex_buffer, lod_id)
+    fn load_lod(&mut self, vertices:Vec<TraceVertex>, indices:Vec<u32>, lod_id:TraceLodID) -> Result<(),Error> {
+        self.trace_lods.load(vertices, indices, lod_id)
     }
 
     fn delete_lod(&mut self, lod_id:TraceLodID) -> Result<(),Error> {
@@ -5,6 +5,8 @@ use gfx_gl;
 
 use types::*;
 
+use image;
+
 use gfx::Factory;
 
 use gfx::texture::Size;
@@ -31,11 +33,12 @@ impl Texture for RgbaTexture {
         let width=image_buffer.width() as Size;
         let height=image_buffer.height() as Size;
 
-        let data=image_buffer.into_vec();
+        let levels=mipmaps(image_buffer);
+        let data:Vec<&[u8]>=levels.iter().map(|level| &level[..]).collect();
 
         let (texture, view) = try!( gfx_factory.create_texture_immutable_u8::<gfx::format::Rgba8>(
             Kind::D2(width, height, AaMode::Single),
-            &[&data[..]]
+            &data[..]
         ), Error::CreateTextureError);
 
         let texture=RgbaTexture {
@@ -45,4 +48,24 @@ impl Texture for RgbaTexture {
 
         ok!(texture)
     }
-}
\ No newline at end of file
+}
+
+///Pixels of all mip levels down to 1x1, every level is the previous one halved
+fn mipmaps(image_buffer:RgbaImage) -> Vec<Vec<u8>> {
+    let (mut width, mut height)=image_buffer.dimensions();
+    let mut levels=Vec::new();
+    let mut level=image_buffer;
+
+    while width>1 || height>1 {
+        width=std::cmp::max(width/2, 1);
+        height=std::cmp::max(height/2, 1);
+
+        let next=image::imageops::resize(&level, width, height, image::FilterType::Triangle);
+        levels.push(level.into_raw());
+        level=next;
+    }
+
+    levels.push(level.into_raw());
+
+    levels
+}
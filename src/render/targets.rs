@@ -8,6 +8,7 @@ pub type FinalDepthTargetView = gfx::handle::DepthStencilView<gfx_gl::Resources,
 pub type FinalColorTarget = gfx::BlendTarget<FinalColorFormat>;
 pub type FinalDepthTarget = gfx::DepthTarget<FinalDepthFormat>;
 
+#[derive(Clone)]
 pub struct Targets {
     pub final_color:FinalColorTargetView,
     pub final_depth:FinalDepthTargetView,
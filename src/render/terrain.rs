@@ -0,0 +1,255 @@
+use nes::{ErrorInfo,ErrorInfoTrait};
+use gfx;
+use gfx_gl;
+
+use types::*;
+use consts::*;
+
+use gfx::traits::FactoryExt;
+
+use cgmath::Matrix4;
+use cgmath::SquareMatrix;
+
+use std::collections::HashMap;
+
+use render;
+use process::{Map,Tile};
+use storage::TextureID;
+
+use super::Error;
+use super::Storage;
+use super::Slots;
+use super::Encoder;
+use super::Targets;
+use super::RenderMode;
+use super::mode::{FLOOR_COLOR, WALL_COLOR, HOLE_COLOR};
+use super::storage::ObjectVertex;
+
+///Tiles along each side of a chunk
+pub const CHUNK_SIZE:usize = 8;
+const CHUNKS:usize = (MAP_SIZE + CHUNK_SIZE - 1) / CHUNK_SIZE;
+///Darkening of a corner by every tile around it, that rises above the corner
+const OCCLUSION_STEP:f32 = 0.2;
+///Vertices closer to the grid than this are corners
+const CORNER_EPSILON:f32 = 0.001;
+
+///Tiles with geometry, the flat colour mode draws every kind in its own colour
+#[derive(Copy, Clone, Eq, PartialEq, Hash)]
+enum TileKind {
+    Floor,
+    Wall,
+    Hole,
+}
+
+///Vertices of all tiles of the chunk with the same kind and texture, baked in world space
+struct Batch {
+    kind:TileKind,
+    texture:RgbaTextureID,
+    vertex_buffer:gfx::handle::Buffer<gfx_gl::Resources, ObjectVertex>,
+    slice:gfx::Slice<gfx_gl::Resources>,
+}
+
+struct Chunk {
+    dirty:bool,
+    batches:Vec<Batch>,
+}
+
+///Static terrain mesh. The map is split into chunks, every chunk is drawn with one call per texture
+///instead of one call per tile. Chunks are rebuilt lazily, when the map is created or a tile changes
+pub struct Terrain {
+    chunks:Vec<Chunk>,
+}
+
+impl Terrain {
+    pub fn new() -> Self {
+        let chunks=(0..CHUNKS*CHUNKS).map(|_| Chunk {
+            dirty:true,
+            batches:Vec::new(),
+        }).collect();
+
+        Terrain {
+            chunks
+        }
+    }
+
+    ///Marks chunks with the tile and its neighbours, because walls and holes connect to the neighbour tiles
+    pub fn invalidate(&mut self, x:usize, z:usize) {
+        for nz in z.saturating_sub(1)..z+2 {
+            for nx in x.saturating_sub(1)..x+2 {
+                if nx<MAP_SIZE && nz<MAP_SIZE {
+                    self.chunks[(nz/CHUNK_SIZE)*CHUNKS + nx/CHUNK_SIZE].dirty=true;
+                }
+            }
+        }
+    }
+
+    ///Ambient occlusion is baked into vertices, all chunks are rebuilt, when it is switched
+    pub fn invalidate_all(&mut self) {
+        for chunk in self.chunks.iter_mut() {
+            chunk.dirty=true;
+        }
+    }
+
+    ///Rebuilds dirty chunks, must be called before the draw
+    pub fn update(&mut self, map:&Map, slots:&Slots, storage:&mut Storage, ambient_occlusion:bool) -> Result<(),Error> {
+        for i in 0..self.chunks.len() {
+            if !self.chunks[i].dirty {
+                continue;
+            }
+
+            let batches=build_chunk(map, slots, storage, i%CHUNKS, i/CHUNKS, ambient_occlusion)?;
+
+            self.chunks[i].batches=batches;
+            self.chunks[i].dirty=false;
+        }
+
+        ok!()
+    }
+
+    pub fn draw(&self, storage:&Storage, encoder:&mut Encoder, targets:&Targets, mode:RenderMode) -> Result<(),Error> {
+        let model_matrix=Matrix4::<f32>::identity();
+
+        for chunk in self.chunks.iter() {
+            for batch in chunk.batches.iter() {
+                if mode.is_textured() {
+                    let texture=storage.textures_rgba.get(batch.texture)?;
+
+                    let data = render::pipelines::ObjectPipeline::Data {
+                        globals: storage.object_globals.clone(),
+                        model_matrix: model_matrix.into(),
+                        texture: (texture.view.clone(), storage.object_pso.sampler.clone()),
+                        vbuf: batch.vertex_buffer.clone(),
+
+                        color_target: targets.final_color.clone(),
+                        depth_target: targets.final_depth.clone()
+                    };
+
+                    encoder.draw(&batch.slice, &storage.object_pso.pso, &data);
+                    storage.count_draw_call();
+                }
+
+                let color=match batch.kind {
+                    TileKind::Floor => FLOOR_COLOR,
+                    TileKind::Wall => WALL_COLOR,
+                    TileKind::Hole => HOLE_COLOR,
+                };
+
+                mode.draw_debug(storage, encoder, targets, &batch.vertex_buffer, &batch.slice, model_matrix, color);
+            }
+        }
+
+        ok!()
+    }
+}
+
+///Mesh and texture index of the tile. Walls and holes have 16 variants, every bit of the mask
+///means, that the neighbour on this side is not of the same kind and the side has to be closed
+pub fn tile_mesh(map:&Map, slots:&Slots, x:usize, z:usize) -> Option<(TerrainMeshID, usize)> {
+    let mask=|same:&Fn(&Tile) -> bool| {
+        let r=if x<MAP_SIZE-1 && same(&map.tiles[x+1][z]) {0}else{1<<0};
+        let l=if x>0 && same(&map.tiles[x-1][z]) {0}else{1<<1};
+        let f=if z<MAP_SIZE-1 && same(&map.tiles[x][z+1]) {0}else{1<<2};
+        let b=if z>0 && same(&map.tiles[x][z-1]) {0}else{1<<3};
+
+        r | l | f | b
+    };
+
+    match map.tiles[x][z] {
+        Tile::Air => None,
+        Tile::Floor(index) => Some((slots.floor_mesh, index)),
+        Tile::Wall(index) => Some((slots.wall_meshes[mask(&|tile:&Tile| tile.is_wall())], index)),
+        Tile::Hole(index) => Some((slots.hole_meshes[mask(&|tile:&Tile| tile.is_hole())], index)),
+    }
+}
+
+///Brightness of the vertex in world space. Corners on the ground are darkened by walls around them,
+///corners at the bottom of a hole by the tiles, that are not holes
+fn occlusion(map:&Map, x:f32, y:f32, z:f32) -> f32 {
+    let (corner_x, corner_z)=(x.round(), z.round());
+
+    if (x-corner_x).abs()>CORNER_EPSILON || (z-corner_z).abs()>CORNER_EPSILON || y>CORNER_EPSILON {
+        return 1.0;
+    }
+
+    let (corner_x, corner_z)=(corner_x as i32, corner_z as i32);
+    let mut occluders=0;
+
+    for &(dx,dz) in [(-1,-1), (0,-1), (-1,0), (0,0)].iter() {
+        let (tx,tz)=(corner_x+dx, corner_z+dz);
+
+        if tx<0 || tz<0 || tx>=MAP_SIZE as i32 || tz>=MAP_SIZE as i32 {
+            continue;
+        }
+
+        let tile=&map.tiles[tx as usize][tz as usize];
+
+        let occludes=if y< -CORNER_EPSILON {
+            !tile.is_hole()
+        }else{
+            tile.is_wall()
+        };
+
+        if occludes {
+            occluders+=1;
+        }
+    }
+
+    1.0-OCCLUSION_STEP*occluders as f32
+}
+
+fn build_chunk(map:&Map, slots:&Slots, storage:&mut Storage, chunk_x:usize, chunk_z:usize, ambient_occlusion:bool) -> Result<Vec<Batch>,Error> {
+    let mut geometry:HashMap<(TileKind,usize),(Vec<ObjectVertex>,Vec<u32>)>=HashMap::new();
+
+    for z in chunk_z*CHUNK_SIZE..MAP_SIZE.min((chunk_z+1)*CHUNK_SIZE) {
+        for x in chunk_x*CHUNK_SIZE..MAP_SIZE.min((chunk_x+1)*CHUNK_SIZE) {
+            let (mesh_id,index)=match tile_mesh(map, slots, x, z) {
+                Some(tile_mesh) => tile_mesh,
+                None => continue,
+            };
+
+            let kind=match map.tiles[x][z] {
+                Tile::Wall(_) => TileKind::Wall,
+                Tile::Hole(_) => TileKind::Hole,
+                _ => TileKind::Floor,
+            };
+
+            let lod=storage.object_lods.get(storage.terrain_meshes.get(mesh_id)?.lod)?;
+            let &mut (ref mut vertices, ref mut indices)=geometry.entry((kind,index)).or_insert_with(|| (Vec::new(), Vec::new()));
+            let base=vertices.len() as u32;
+
+            for vertex in lod.vertices.iter() {
+                let mut vertex=*vertex;
+                vertex.pos[0]+=x as f32;
+                vertex.pos[2]+=z as f32;
+
+                if ambient_occlusion {
+                    let brightness=occlusion(map, vertex.pos[0], vertex.pos[1], vertex.pos[2]);
+
+                    for channel in vertex.color.iter_mut() {
+                        *channel*=brightness;
+                    }
+                }
+
+                vertices.push(vertex);
+            }
+
+            indices.extend(lod.indices.iter().map(|index| base+index));
+        }
+    }
+
+    let mut batches=Vec::with_capacity(geometry.len());
+
+    for ((kind,index),(vertices,indices)) in geometry {
+        let (vertex_buffer, slice) = storage.gfx_factory.create_vertex_buffer_with_slice(&vertices[..], &indices[..]);
+
+        batches.push(Batch {
+            kind,
+            //Unknown textures are reported by the draw as missing resources
+            texture:slots.terrain_textures.get(index).cloned().unwrap_or(RgbaTextureID::zeroed()),
+            vertex_buffer,
+            slice
+        });
+    }
+
+    ok!(batches)
+}
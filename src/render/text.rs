@@ -0,0 +1,254 @@
+use nes::{ErrorInfo,ErrorInfoTrait};
+use gfx;
+use gfx_gl;
+use rusttype;
+
+use gfx::Factory;
+use gfx::texture::{Kind, AaMode, Size};
+
+use rusttype::{FontCollection, Scale, point};
+
+use std::collections::HashMap;
+
+use render;
+
+use super::Error;
+use super::Storage;
+use super::Encoder;
+use super::Targets;
+use super::DynamicBuffer;
+use super::pipelines::TextVertex;
+
+static FONT:&'static [u8] = include_bytes!("../../fonts/DejaVuSansMono.ttf");
+
+const FONT_SIZE:f32 = 16.0;
+const ATLAS_WIDTH:usize = 256;
+///Glyphs are separated by empty pixels, so bilinear filtering does not catch the neighbours
+const PADDING:usize = 1;
+///Opaque block in the corner of the atlas, rectangles are drawn with its center
+const SOLID_SIZE:usize = 4;
+
+struct Glyph {
+    uv_min:[f32;2],
+    uv_max:[f32;2],
+    ///Top left corner relative to the pen on the baseline
+    offset:[f32;2],
+    size:[f32;2],
+    advance:f32,
+}
+
+///Glyph atlas of printable ASCII characters, rasterized once from the embedded font. Text is laid out
+///into quads in pixels from the top left corner of the screen and drawn with one call
+pub struct Text {
+    glyphs:HashMap<char,Glyph>,
+    solid_uv:[f32;2],
+    ascent:f32,
+    pub line_height:f32,
+    view:gfx::handle::ShaderResourceView<gfx_gl::Resources, [f32; 4]>,
+    vertex_buffer:DynamicBuffer<TextVertex>,
+}
+
+impl Text {
+    pub fn new(gfx_factory:&mut gfx_gl::Factory) -> Result<Self,Error> {
+        let font=match FontCollection::from_bytes(FONT).into_font() {
+            Some(font) => font,
+            None => return err!(Error::FontError, "font collection is empty".to_string()),
+        };
+
+        let scale=Scale::uniform(FONT_SIZE);
+        let v_metrics=font.v_metrics(scale);
+
+        //Glyphs are packed into rows from left to right, the height of a row is the height of its tallest glyph
+        let mut rasters=Vec::new();
+        let (mut x, mut y, mut row_height)=(SOLID_SIZE+PADDING, 0, SOLID_SIZE);
+
+        for code in 32u8..127 {
+            let ch=code as char;
+
+            let glyph=match font.glyph(rusttype::Codepoint(code as u32)) {
+                Some(glyph) => glyph.scaled(scale),
+                None => continue,
+            };
+
+            let advance=glyph.h_metrics().advance_width;
+            let glyph=glyph.positioned(point(0.0, 0.0));
+
+            let bounding_box=match glyph.pixel_bounding_box() {
+                Some(bounding_box) => bounding_box,
+                None => {
+                    //Space has no pixels
+                    rasters.push((ch, advance, None));
+                    continue;
+                }
+            };
+
+            let width=(bounding_box.max.x-bounding_box.min.x) as usize;
+            let height=(bounding_box.max.y-bounding_box.min.y) as usize;
+
+            if x+width>ATLAS_WIDTH {
+                x=0;
+                y+=row_height+PADDING;
+                row_height=0;
+            }
+
+            let mut pixels=vec![0u8; width*height];
+            glyph.draw(|gx, gy, coverage| pixels[gy as usize*width + gx as usize]=(coverage*255.0) as u8);
+
+            rasters.push((ch, advance, Some((x, y, width, height, bounding_box.min.x, bounding_box.min.y, pixels))));
+
+            x+=width+PADDING;
+            row_height=row_height.max(height);
+        }
+
+        let atlas_height=(y+row_height).next_power_of_two();
+        let mut data=vec![0u8; ATLAS_WIDTH*atlas_height*4];
+
+        let (atlas_width, atlas_height_f)=(ATLAS_WIDTH as f32, atlas_height as f32);
+        let mut glyphs=HashMap::new();
+
+        {
+            let mut set_alpha=|x:usize, y:usize, alpha:u8| {
+                let i=(y*ATLAS_WIDTH + x)*4;
+                data[i]=255;
+                data[i+1]=255;
+                data[i+2]=255;
+                data[i+3]=alpha;
+            };
+
+            for sy in 0..SOLID_SIZE {
+                for sx in 0..SOLID_SIZE {
+                    set_alpha(sx, sy, 255);
+                }
+            }
+
+            for (ch, advance, raster) in rasters {
+                let glyph=match raster {
+                    Some((x, y, width, height, min_x, min_y, pixels)) => {
+                        for gy in 0..height {
+                            for gx in 0..width {
+                                set_alpha(x+gx, y+gy, pixels[gy*width + gx]);
+                            }
+                        }
+
+                        Glyph {
+                            uv_min:[x as f32/atlas_width, y as f32/atlas_height_f],
+                            uv_max:[(x+width) as f32/atlas_width, (y+height) as f32/atlas_height_f],
+                            offset:[min_x as f32, min_y as f32],
+                            size:[width as f32, height as f32],
+                            advance
+                        }
+                    },
+                    None => Glyph {
+                        uv_min:[0.0, 0.0],
+                        uv_max:[0.0, 0.0],
+                        offset:[0.0, 0.0],
+                        size:[0.0, 0.0],
+                        advance
+                    },
+                };
+
+                glyphs.insert(ch, glyph);
+            }
+        }
+
+        let (_, view) = try!( gfx_factory.create_texture_immutable_u8::<gfx::format::Rgba8>(
+            Kind::D2(ATLAS_WIDTH as Size, atlas_height as Size, AaMode::Single),
+            &[&data[..]]
+        ), Error::CreateTextureError);
+
+        let solid_center=SOLID_SIZE as f32/2.0;
+
+        let text=Text {
+            glyphs,
+            solid_uv:[solid_center/atlas_width, solid_center/atlas_height_f],
+            ascent:v_metrics.ascent,
+            line_height:(v_metrics.ascent - v_metrics.descent + v_metrics.line_gap).ceil(),
+            view,
+            vertex_buffer:DynamicBuffer::new(),
+        };
+
+        ok!(text)
+    }
+
+    ///Width of the line in pixels
+    pub fn measure(&self, line:&str) -> f32 {
+        line.chars().map(|ch| self.glyph(ch).map(|glyph| glyph.advance).unwrap_or(0.0)).sum()
+    }
+
+    ///Adds quads of the line, x and y are the top left corner of the line
+    pub fn layout(&self, line:&str, x:f32, y:f32, color:[f32;4], vertices:&mut Vec<TextVertex>) {
+        let baseline=y+self.ascent;
+        let mut pen=x;
+
+        for ch in line.chars() {
+            let glyph=match self.glyph(ch) {
+                Some(glyph) => glyph,
+                None => continue,
+            };
+
+            if glyph.size[0]>0.0 {
+                let left=(pen+glyph.offset[0]).round();
+                let top=(baseline+glyph.offset[1]).round();
+
+                push_quad(vertices,
+                    [left, top], [left+glyph.size[0], top+glyph.size[1]],
+                    glyph.uv_min, glyph.uv_max, color
+                );
+            }
+
+            pen+=glyph.advance;
+        }
+    }
+
+    ///Adds a plain rectangle, for example a panel behind the text
+    pub fn layout_rect(&self, x:f32, y:f32, width:f32, height:f32, color:[f32;4], vertices:&mut Vec<TextVertex>) {
+        push_quad(vertices, [x, y], [x+width, y+height], self.solid_uv, self.solid_uv, color);
+    }
+
+    pub fn draw(&mut self, storage:&Storage, encoder:&mut Encoder, targets:&Targets, screen_size:(u32,u32), vertices:&[TextVertex]) {
+        if vertices.is_empty() {
+            return;
+        }
+
+        let vertex_buffer=match self.vertex_buffer.upload(&mut storage.gfx_factory.clone(), encoder, vertices) {
+            Ok(vertex_buffer) => vertex_buffer,
+            Err(error) => {
+                println!("Text error: {}", error);
+                return;
+            }
+        };
+
+        let slice=gfx::Slice {
+            start:0,
+            end:vertices.len() as u32,
+            base_vertex:0,
+            instances:None,
+            buffer:gfx::IndexBuffer::Auto,
+        };
+
+        let data = render::pipelines::TextPipeline::Data {
+            screen_size: [screen_size.0 as f32, screen_size.1 as f32],
+            texture: (self.view.clone(), storage.text_pso.sampler.clone()),
+            vbuf: vertex_buffer,
+
+            color_target: targets.final_color.clone(),
+        };
+
+        encoder.draw(&slice, &storage.text_pso.pso, &data);
+        storage.count_draw_call();
+    }
+
+    ///Unknown characters are drawn as '?'
+    fn glyph(&self, ch:char) -> Option<&Glyph> {
+        self.glyphs.get(&ch).or_else(|| self.glyphs.get(&'?'))
+    }
+}
+
+fn push_quad(vertices:&mut Vec<TextVertex>, min:[f32;2], max:[f32;2], uv_min:[f32;2], uv_max:[f32;2], color:[f32;4]) {
+    let top_left=TextVertex::new(min[0], min[1], uv_min, color);
+    let top_right=TextVertex::new(max[0], min[1], [uv_max[0], uv_min[1]], color);
+    let bottom_left=TextVertex::new(min[0], max[1], [uv_min[0], uv_max[1]], color);
+    let bottom_right=TextVertex::new(max[0], max[1], uv_max, color);
+
+    vertices.extend_from_slice(&[top_left, bottom_left, top_right, top_right, bottom_left, bottom_right]);
+}
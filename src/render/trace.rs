@@ -2,19 +2,32 @@ use nes::{ErrorInfo,ErrorInfoTrait};
 
 use types::*;
 
+use cgmath::{Matrix4,Vector3,Rad};
+
 use object_pool::growable::Pool;
 
 use super::Error;
 use super::Storage;
+use super::Slots;
 use super::Encoder;
 use super::Targets;
+use super::DynamicBuffer;
+use super::pipelines::TraceInstance;
+
+///Height of the traces above the ground
+const TRACE_Y:f32 = 0.6;
+const DEFAULT_THICKNESS:f32 = 0.1;
+const MIN_THICKNESS:f32 = 0.02;
+const MAX_THICKNESS:f32 = 0.5;
+///Length of the arrowhead in thicknesses
+const HEAD_LENGTH:f32 = 3.0;
 
 pub struct Trace {
     x:u32,
     z:u32,
     angle:f32,
+    len:f32,
     color:[f32;4],
-    mesh_id:TraceMeshID
 }
 
 impl Trace {
@@ -22,27 +35,34 @@ impl Trace {
         x:u32,
         z:u32,
         angle:f32,
-        color:[f32;4],
-        mesh_id:TraceMeshID
+        len:f32,
+        color:[f32;4]
     ) -> Self {
         Trace {
             x,
             z,
             angle,
-            color,
-            mesh_id
+            len,
+            color
         }
     }
 }
 
+///Traces are instances of the unit body and arrowhead meshes, the length and the thickness are applied by the model matrix
 pub struct TracePool {
     pool:Pool<Trace, Trace>,
+    thickness:f32,
+    body_instances:DynamicBuffer<TraceInstance>,
+    head_instances:DynamicBuffer<TraceInstance>,
 }
 
 impl TracePool {
     pub fn new() -> Self {
         TracePool {
-            pool:Pool::new()
+            pool:Pool::new(),
+            thickness:DEFAULT_THICKNESS,
+            body_instances:DynamicBuffer::new(),
+            head_instances:DynamicBuffer::new(),
         }
     }
 
@@ -58,14 +78,42 @@ impl TracePool {
         self.pool.get_mut(id.get_id()).unwrap().color=color;
     }
 
-    pub fn draw(&self, storage:&Storage, encoder:&mut Encoder, targets:&Targets ) -> Result<(),Error> {
+    pub fn scale_thickness(&mut self, factor:f32) -> f32 {
+        self.thickness=(self.thickness*factor).max(MIN_THICKNESS).min(MAX_THICKNESS);
+        self.thickness
+    }
+
+    pub fn draw(&mut self, storage:&Storage, encoder:&mut Encoder, targets:&Targets, slots:&Slots) -> Result<(),Error> {
+        let mut bodies=Vec::new();
+        let mut heads=Vec::new();
+
         for trace in self.pool.iter() {
-            storage.trace_meshes.get(trace.mesh_id)?.draw(
-                storage, encoder, targets,
-                trace.x, trace.z, trace.angle, trace.color
-            )?;
+            //Short traces are drawn as an arrowhead only
+            let head_length=(HEAD_LENGTH*self.thickness).min(trace.len);
+            let body_length=trace.len-head_length;
+
+            let base_matrix=Matrix4::from_translation(Vector3::new(trace.x as f32, TRACE_Y, trace.z as f32))*
+                Matrix4::from_angle_y(Rad(trace.angle));
+
+            if body_length>0.0 {
+                let body_matrix=base_matrix*Matrix4::from_nonuniform_scale(self.thickness, self.thickness, body_length);
+                bodies.push(TraceInstance::new(body_matrix.into(), trace.color));
+            }
+
+            let head_matrix=base_matrix*
+                Matrix4::from_translation(Vector3::new(0.0, 0.0, body_length))*
+                Matrix4::from_nonuniform_scale(self.thickness, self.thickness, head_length);
+
+            heads.push(TraceInstance::new(head_matrix.into(), trace.color));
+        }
+
+        if heads.is_empty() {
+            return ok!();
         }
 
+        storage.trace_meshes.get(slots.trace_body)?.draw_instanced(storage, encoder, targets, &bodies, &mut self.body_instances)?;
+        storage.trace_meshes.get(slots.trace_head)?.draw_instanced(storage, encoder, targets, &heads, &mut self.head_instances)?;
+
         ok!()
     }
-}
\ No newline at end of file
+}
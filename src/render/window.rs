@@ -27,6 +27,10 @@ impl Window {
         ok!()
     }
 
+    pub fn get_size(&self) -> (u32,u32) {
+        (self.width, self.height)
+    }
+
     pub fn resize(&mut self, width:u32, height:u32, targets:&mut Targets) {
         self.window.resize(width,height);
         gfx_glutin::update_views(&self.window, &mut targets.final_color, &mut targets.final_depth);
@@ -4,6 +4,8 @@ use reactor;
 
 use types::ThreadSource;
 
+use super::Handle;
+
 define_error!( Error,
     BrockenChannel(error:Box<reactor::BrockenChannel<ThreadSource>>) =>
         "{}",
@@ -14,5 +16,25 @@ define_error!( Error,
     OpenImageFileError(file_name:String) =>
         "Can not open image \"{}\"",
     ReadImageFileError(file_name:String) =>
-        "Can not read image \"{}\""
+        "Can not read image \"{}\"",
+    UnknownImageFormatError(file_name:String) =>
+        "Can not detect format of image \"{1}\", expected PNG, JPEG, BMP or TGA",
+    UnsupportedImageFormatError(file_name:String, format:String) =>
+        "Image \"{1}\" has unsupported format {2}, expected PNG, JPEG, BMP or TGA",
+    DecodeImageError(file_name:String, message:String) =>
+        "Can not decode image \"{1}\": {2}",
+
+    OpenModelFileError(file_name:String) =>
+        "Can not open model \"{1}\"",
+    ReadModelFileError(file_name:String) =>
+        "Can not read model \"{1}\"",
+    ParseObjError(file_name:String, line:usize, message:String) =>
+        "Can not load model \"{1}\", line {2}: {3}",
+
+    NoTexture(handle:Handle) =>
+        "Texture {1} does not exist",
+    NoMesh(handle:Handle) =>
+        "Mesh {1} does not exist",
+    NoLod(handle:Handle) =>
+        "Lod {1} does not exist"
 );
\ No newline at end of file
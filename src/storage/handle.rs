@@ -0,0 +1,197 @@
+use std;
+
+///Resource handle. Process side chooses it and render side stores the resource under it, so both halves
+///do not depend on the order of inserts. The generation distinguishes reused slots: a handle of a deleted
+///resource never finds the resource, that has taken its slot
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Handle {
+    index:u32,
+    generation:u32,
+}
+
+impl Handle {
+    ///Generation 0 is never issued, so the zeroed handle points to nothing
+    pub fn zeroed() -> Self {
+        Handle {
+            index:0,
+            generation:0
+        }
+    }
+
+    pub fn get_index(&self) -> usize {
+        self.index as usize
+    }
+
+    pub fn get_generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+impl std::fmt::Display for Handle{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}v{}", self.index, self.generation)
+    }
+}
+
+///Issues handles on the process side
+pub struct HandleAllocator {
+    generations:Vec<u32>,
+    alive:Vec<bool>,
+    free:Vec<u32>,
+}
+
+impl HandleAllocator {
+    pub fn new() -> Self {
+        HandleAllocator {
+            generations:Vec::new(),
+            alive:Vec::new(),
+            free:Vec::new(),
+        }
+    }
+
+    pub fn insert(&mut self) -> Handle {
+        let index=match self.free.pop() {
+            Some(index) => index,
+            None => {
+                self.generations.push(0);
+                self.alive.push(false);
+                (self.generations.len()-1) as u32
+            }
+        };
+
+        self.generations[index as usize]+=1;
+        self.alive[index as usize]=true;
+
+        Handle {
+            index,
+            generation:self.generations[index as usize]
+        }
+    }
+
+    ///Returns false for a stale or unknown handle
+    pub fn remove(&mut self, handle:Handle) -> bool {
+        let index=handle.get_index();
+
+        if index>=self.generations.len() || !self.alive[index] || self.generations[index]!=handle.generation {
+            return false;
+        }
+
+        self.alive[index]=false;
+        self.free.push(handle.index);
+
+        true
+    }
+}
+
+///Resources keyed by handles on the render side
+pub struct HandleMap<T> {
+    entries:Vec<Option<(u32,T)>>,
+}
+
+impl<T> HandleMap<T> {
+    pub fn new() -> Self {
+        HandleMap {
+            entries:Vec::new()
+        }
+    }
+
+    ///Stores the resource under the handle. Process side reuses only released slots, so the returned
+    ///resource, that has occupied the slot, means a lost delete
+    pub fn insert(&mut self, handle:Handle, value:T) -> Option<T> {
+        let index=handle.get_index();
+
+        while self.entries.len()<=index {
+            self.entries.push(None);
+        }
+
+        std::mem::replace(&mut self.entries[index], Some((handle.generation, value))).map(|(_,value)| value)
+    }
+
+    pub fn get(&self, handle:Handle) -> Option<&T> {
+        match self.entries.get(handle.get_index()) {
+            Some(&Some((generation, ref value))) if generation==handle.generation => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn remove(&mut self, handle:Handle) -> Option<T> {
+        if self.get(handle).is_none() {
+            return None;
+        }
+
+        self.entries[handle.get_index()].take().map(|(_,value)| value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removed_slot_is_reused_with_new_generation() {
+        let mut allocator=HandleAllocator::new();
+
+        let first=allocator.insert();
+        assert!(allocator.remove(first));
+
+        let second=allocator.insert();
+
+        assert_eq!(second.get_index(), first.get_index());
+        assert!(second.get_generation()>first.get_generation());
+    }
+
+    #[test]
+    fn stale_or_unknown_handle_is_not_removed() {
+        let mut allocator=HandleAllocator::new();
+
+        let first=allocator.insert();
+        assert!(allocator.remove(first));
+        allocator.insert();
+
+        assert!(!allocator.remove(first));
+        assert!(!allocator.remove(Handle::zeroed()));
+    }
+
+    #[test]
+    fn stale_handle_does_not_find_resource_in_reused_slot() {
+        let mut allocator=HandleAllocator::new();
+        let mut map=HandleMap::new();
+
+        let first=allocator.insert();
+        assert!(map.insert(first, "first").is_none());
+        allocator.remove(first);
+        map.remove(first);
+
+        let second=allocator.insert();
+        assert!(map.insert(second, "second").is_none());
+
+        assert_eq!(map.get(first), None);
+        assert_eq!(map.remove(first), None);
+        assert_eq!(map.get(second), Some(&"second"));
+    }
+
+    #[test]
+    fn insert_over_live_resource_returns_it() {
+        let mut allocator=HandleAllocator::new();
+        let mut map=HandleMap::new();
+
+        let first=allocator.insert();
+        map.insert(first, 1);
+
+        //The delete of the first resource has been lost
+        let second=Handle { index:first.index, generation:first.generation+1 };
+
+        assert_eq!(map.insert(second, 2), Some(1));
+        assert_eq!(map.get(second), Some(&2));
+    }
+
+    #[test]
+    fn zeroed_handle_finds_nothing() {
+        let mut allocator=HandleAllocator::new();
+        let mut map=HandleMap::new();
+
+        map.insert(allocator.insert(), 1);
+
+        assert_eq!(map.get(Handle::zeroed()), None);
+    }
+}
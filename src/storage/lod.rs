@@ -1,23 +1,23 @@
 
-use object_pool::growable::ID;
+use super::Handle;
 
 pub trait LodID {
-    fn new(id:ID) -> Self;
-    fn get_id(&self) -> ID;
+    fn new(id:Handle) -> Self;
+    fn get_id(&self) -> Handle;
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
-pub struct ObjectLodID(ID);
+pub struct ObjectLodID(Handle);
 
 impl LodID for ObjectLodID {
-    fn new(id:ID) -> Self {ObjectLodID(id)}
-    fn get_id(&self) -> ID {self.0}
+    fn new(id:Handle) -> Self {ObjectLodID(id)}
+    fn get_id(&self) -> Handle {self.0}
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
-pub struct TraceLodID(ID);
+pub struct TraceLodID(Handle);
 
 impl LodID for TraceLodID {
-    fn new(id:ID) -> Self {TraceLodID(id)}
-    fn get_id(&self) -> ID {self.0}
+    fn new(id:Handle) -> Self {TraceLodID(id)}
+    fn get_id(&self) -> Handle {self.0}
 }
\ No newline at end of file
@@ -1,35 +1,35 @@
 
-use object_pool::growable::ID;
+use super::Handle;
 
 pub trait MeshID {
-    fn new(id:ID) -> Self;
+    fn new(id:Handle) -> Self;
     fn zeroed() -> Self;
-    fn get_id(&self) -> ID;
+    fn get_id(&self) -> Handle;
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
-pub struct ObjectMeshID(ID);
+pub struct ObjectMeshID(Handle);
 
 impl MeshID for ObjectMeshID {
-    fn new(id:ID) -> Self {ObjectMeshID(id)}
-    fn zeroed() -> Self {ObjectMeshID(ID::zeroed())}
-    fn get_id(&self) -> ID {self.0}
+    fn new(id:Handle) -> Self {ObjectMeshID(id)}
+    fn zeroed() -> Self {ObjectMeshID(Handle::zeroed())}
+    fn get_id(&self) -> Handle {self.0}
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
-pub struct TerrainMeshID(ID);
+pub struct TerrainMeshID(Handle);
 
 impl MeshID for TerrainMeshID {
-    fn new(id:ID) -> Self {TerrainMeshID(id)}
-    fn zeroed() -> Self {TerrainMeshID(ID::zeroed())}
-    fn get_id(&self) -> ID {self.0}
+    fn new(id:Handle) -> Self {TerrainMeshID(id)}
+    fn zeroed() -> Self {TerrainMeshID(Handle::zeroed())}
+    fn get_id(&self) -> Handle {self.0}
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
-pub struct TraceMeshID(ID);
+pub struct TraceMeshID(Handle);
 
 impl MeshID for TraceMeshID {
-    fn new(id:ID) -> Self {TraceMeshID(id)}
-    fn zeroed() -> Self {TraceMeshID(ID::zeroed())}
-    fn get_id(&self) -> ID {self.0}
+    fn new(id:Handle) -> Self {TraceMeshID(id)}
+    fn zeroed() -> Self {TraceMeshID(Handle::zeroed())}
+    fn get_id(&self) -> Handle {self.0}
 }
\ No newline at end of file
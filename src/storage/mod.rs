@@ -3,6 +3,9 @@
 pub mod error;
 pub use self::error::Error;
 
+pub mod handle;
+pub use self::handle::{Handle, HandleAllocator, HandleMap};
+
 pub mod storage;
 pub use self::storage::{Storage, TextureStorage, MeshStorage, LodStorage};
 
@@ -10,6 +13,9 @@ pub mod texture;
 pub use self::texture::TextureID;
 pub use self::texture::{RgbaTexture, RgbaTextureID};
 
+pub mod obj;
+pub use self::obj::{ObjModel, ObjPart};
+
 pub mod mesh;
 pub use self::mesh::{MeshID, ObjectMeshID, TerrainMeshID, TraceMeshID};
 
@@ -0,0 +1,335 @@
+use std;
+use nes::{ErrorInfo,ErrorInfoTrait};
+use image;
+
+use types::*;
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use render::storage::ObjectVertex;
+use render::storage::smooth_normals;
+
+use super::Error;
+use super::Storage;
+use super::{TextureStorage,LodStorage};
+use super::RgbaTexture;
+
+///Material of the .mtl library. Materials without map_Kd are drawn with a plain texture of the Kd color
+struct Material {
+    texture_file:Option<String>,
+    color:[f32;3],
+}
+
+impl Material {
+    fn new() -> Self {
+        Material {
+            texture_file:None,
+            color:[1.0, 1.0, 1.0]
+        }
+    }
+}
+
+///Triangles of one object, that use the same material
+struct Part {
+    name:String,
+    material:Option<String>,
+    vertices:Vec<ObjectVertex>,
+    indices:Vec<u32>,
+    ///Index of the vertex for every used pair of position and uv
+    known:HashMap<(usize,Option<usize>),u32>,
+}
+
+///Loaded part of the model, named "object/material"
+pub struct ObjPart {
+    pub name:String,
+    pub lod:ObjectLodID,
+    pub texture:RgbaTextureID,
+}
+
+///Wavefront OBJ loader. Every object or group is split by materials into parts, because
+///an ObjectMesh has one texture. Normals are computed from the faces, vn lines are skipped
+pub struct ObjModel {}
+
+impl ObjModel {
+    ///Loads indexed lods of all parts of the model
+    pub fn load(file_name:&str, storage:&Storage) -> Result<Vec<ObjPart>,Error> {
+        let text=read_file(file_name)?;
+        let directory=Path::new(file_name).parent().unwrap_or(Path::new(""));
+
+        let mut positions:Vec<[f32;3]>=Vec::new();
+        let mut uvs:Vec<[f32;2]>=Vec::new();
+        let mut materials:HashMap<String,Material>=HashMap::new();
+        let mut parts:Vec<Part>=Vec::new();
+
+        let mut object="default".to_string();
+        let mut material:Option<String>=None;
+
+        for (i,line) in text.lines().enumerate() {
+            let line_number=i+1;
+            let line=match line.find('#') {
+                Some(comment) => &line[..comment],
+                None => line,
+            };
+
+            let mut words=line.split_whitespace();
+
+            let keyword=match words.next() {
+                Some(keyword) => keyword,
+                None => continue,
+            };
+
+            let args:Vec<&str>=words.collect();
+
+            match keyword {
+                "v" => {
+                    let v=parse_floats(file_name, line_number, &args, 3)?;
+                    positions.push([v[0], v[1], v[2]]);
+                },
+                "vt" => {
+                    let vt=parse_floats(file_name, line_number, &args, 2)?;
+                    //OBJ has v=0 at the bottom of the image, textures are uploaded from the top row
+                    uvs.push([vt[0], 1.0-vt[1]]);
+                },
+                "o" | "g" => {
+                    object=if args.is_empty() {"default".to_string()} else {args.join(" ")};
+                },
+                "usemtl" => {
+                    material=args.first().map(|name| name.to_string());
+                },
+                "mtllib" => {
+                    for library in args.iter() {
+                        let library_file=directory.join(library);
+                        load_materials(library_file.to_string_lossy().as_ref(), &mut materials)?;
+                    }
+                },
+                "f" => {
+                    if args.len()<3 {
+                        return err!(Error::ParseObjError, file_name.to_string(), line_number, "face has less than 3 vertices".to_string());
+                    }
+
+                    let mut face=Vec::with_capacity(args.len());
+
+                    for arg in args.iter() {
+                        face.push(parse_face_vertex(file_name, line_number, arg, positions.len(), uvs.len())?);
+                    }
+
+                    //o and usemtl may return to an earlier pair, its faces go to the same part
+                    let existing=parts.iter().position(|part| part.name==object && part.material==material);
+
+                    let part_index=match existing {
+                        Some(part_index) => part_index,
+                        None => {
+                            parts.push(Part {
+                                name:object.clone(),
+                                material:material.clone(),
+                                vertices:Vec::new(),
+                                indices:Vec::new(),
+                                known:HashMap::new(),
+                            });
+
+                            parts.len()-1
+                        }
+                    };
+
+                    let part=&mut parts[part_index];
+                    let mut face_indices=Vec::with_capacity(face.len());
+
+                    for &(position,uv) in face.iter() {
+                        let vertices=&mut part.vertices;
+
+                        let index=*part.known.entry((position,uv)).or_insert_with(|| {
+                            vertices.push(ObjectVertex::with_uv(positions[position], uv.map(|uv| uvs[uv]).unwrap_or([0.0, 0.0])));
+
+                            (vertices.len()-1) as u32
+                        });
+
+                        face_indices.push(index);
+                    }
+
+                    //Polygons are convex in practice, so a fan is enough
+                    for j in 1..face_indices.len()-1 {
+                        part.indices.push(face_indices[0]);
+                        part.indices.push(face_indices[j]);
+                        part.indices.push(face_indices[j+1]);
+                    }
+                },
+                _ => {}, //vn, s, l and others are not needed
+            }
+        }
+
+        if parts.is_empty() {
+            return err!(Error::ParseObjError, file_name.to_string(), 0, "model has no faces".to_string());
+        }
+
+        let mut textures:HashMap<Option<String>,RgbaTextureID>=HashMap::new();
+        let mut loaded=Vec::with_capacity(parts.len());
+
+        for part in parts {
+            let cached=textures.get(&part.material).cloned();
+
+            let texture_id=match cached {
+                Some(texture_id) => texture_id,
+                None => {
+                    let texture_id=match part.material {
+                        Some(ref name) => match materials.get(name) {
+                            Some(material) => load_material_texture(material, storage)?,
+                            None => return err!(Error::ParseObjError, file_name.to_string(), 0, format!("material \"{}\" is not defined", name)),
+                        },
+                        None => load_material_texture(&Material::new(), storage)?,
+                    };
+
+                    textures.insert(part.material.clone(), texture_id);
+                    texture_id
+                }
+            };
+
+            let mut vertices=part.vertices;
+            smooth_normals(&mut vertices, &part.indices);
+
+            let lod_id=storage.load_indexed_lod(vertices, part.indices)?;
+
+            let name=match part.material {
+                Some(ref material) => format!("{}/{}", part.name, material),
+                None => part.name.clone(),
+            };
+
+            loaded.push(ObjPart {
+                name,
+                lod:lod_id,
+                texture:texture_id
+            });
+        }
+
+        ok!(loaded)
+    }
+}
+
+fn read_file(file_name:&str) -> Result<String,Error> {
+    let mut file = match File::open(file_name) {
+        Ok(file) => file,
+        Err(_) => return err!(Error::OpenModelFileError, file_name.to_string()),
+    };
+
+    let mut text=String::new();
+
+    match file.read_to_string(&mut text) {
+        Ok(_) => ok!(text),
+        Err(_) => err!(Error::ReadModelFileError, file_name.to_string()),
+    }
+}
+
+fn parse_floats(file_name:&str, line_number:usize, args:&[&str], count:usize) -> Result<Vec<f32>,Error> {
+    if args.len()<count {
+        return err!(Error::ParseObjError, file_name.to_string(), line_number, format!("expected {} numbers", count));
+    }
+
+    let mut values=Vec::with_capacity(count);
+
+    for arg in args[..count].iter() {
+        match arg.parse::<f32>() {
+            Ok(value) => values.push(value),
+            Err(_) => return err!(Error::ParseObjError, file_name.to_string(), line_number, format!("\"{}\" is not a number", arg)),
+        }
+    }
+
+    ok!(values)
+}
+
+///Resolves 1-based and negative (relative to the end) indices
+fn resolve_index(file_name:&str, line_number:usize, index:&str, len:usize) -> Result<usize,Error> {
+    let value=match index.parse::<i64>() {
+        Ok(value) => value,
+        Err(_) => return err!(Error::ParseObjError, file_name.to_string(), line_number, format!("\"{}\" is not an index", index)),
+    };
+
+    let resolved=if value>0 {
+        value-1
+    }else{
+        len as i64 + value
+    };
+
+    if value==0 || resolved<0 || resolved>=len as i64 {
+        return err!(Error::ParseObjError, file_name.to_string(), line_number, format!("index {} is out of range", value));
+    }
+
+    ok!(resolved as usize)
+}
+
+///Face vertex is v, v/vt, v//vn or v/vt/vn. Returns indices of the position and the uv
+fn parse_face_vertex(file_name:&str, line_number:usize, arg:&str, positions:usize, uvs:usize) -> Result<(usize,Option<usize>),Error> {
+    let mut indices=arg.split('/');
+
+    let position=match indices.next() {
+        Some(index) => resolve_index(file_name, line_number, index, positions)?,
+        None => return err!(Error::ParseObjError, file_name.to_string(), line_number, format!("\"{}\" is not a face vertex", arg)),
+    };
+
+    let uv=match indices.next() {
+        Some(index) if !index.is_empty() => Some(resolve_index(file_name, line_number, index, uvs)?),
+        _ => None,
+    };
+
+    ok!((position, uv))
+}
+
+fn load_materials(file_name:&str, materials:&mut HashMap<String,Material>) -> Result<(),Error> {
+    let text=read_file(file_name)?;
+    let directory=Path::new(file_name).parent().unwrap_or(Path::new(""));
+
+    let mut current:Option<String>=None;
+
+    for (i,line) in text.lines().enumerate() {
+        let line_number=i+1;
+        let mut words=line.split_whitespace();
+
+        let keyword=match words.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+
+        let args:Vec<&str>=words.collect();
+
+        match keyword {
+            "newmtl" => {
+                let name=args.join(" ");
+                materials.insert(name.clone(), Material::new());
+                current=Some(name);
+            },
+            "Kd" | "map_Kd" => {
+                let material=match current {
+                    Some(ref name) => materials.get_mut(name).unwrap(),
+                    None => return err!(Error::ParseObjError, file_name.to_string(), line_number, format!("{} before newmtl", keyword)),
+                };
+
+                if keyword=="Kd" {
+                    let kd=parse_floats(file_name, line_number, &args, 3)?;
+                    material.color=[kd[0], kd[1], kd[2]];
+                }else{
+                    //Options like -s or -o are not supported, the file name is the last argument
+                    match args.last() {
+                        Some(texture_file) => material.texture_file=Some(directory.join(texture_file).to_string_lossy().into_owned()),
+                        None => return err!(Error::ParseObjError, file_name.to_string(), line_number, "map_Kd has no file".to_string()),
+                    }
+                }
+            },
+            _ => {},
+        }
+    }
+
+    ok!()
+}
+
+fn load_material_texture(material:&Material, storage:&Storage) -> Result<RgbaTextureID,Error> {
+    match material.texture_file {
+        Some(ref texture_file) => RgbaTexture::load(texture_file.as_str(), storage),
+        None => {
+            let to_u8=|c:f32| (c.max(0.0).min(1.0)*255.0) as u8;
+            let color=[to_u8(material.color[0]), to_u8(material.color[1]), to_u8(material.color[2]), 255];
+
+            storage.load_texture(image::ImageBuffer::from_pixel(1, 1, image::Rgba(color)))
+        }
+    }
+}
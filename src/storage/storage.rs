@@ -1,23 +1,25 @@
 use std;
 use nes::{ErrorInfo,ErrorInfoTrait};
-use object_pool;
 
 use types::*;
 
 use std::marker::PhantomData;
+use std::collections::HashMap;
 
 use std::ops::DerefMut;
 use std::sync::{Arc,Mutex};
-use self::object_pool::growable::Pool;
 
 use render::RenderSender;
 use render::{RenderCommand, LoadTexture, LoadMesh, LoadLod};
+use render::{DeleteTexture, DeleteMesh, DeleteLod};
 
 use render::storage::{ObjectVertex,TraceVertex};
+use render::storage::{VertexKey,index_vertices};
 use render::storage::{ObjectMesh,TerrainMesh,TraceMesh};
 
 use super::Error;
 use super::{TextureID, MeshID, LodID};
+use super::HandleAllocator;
 
 
 pub trait TextureStorage<ID:TextureID,IB> {
@@ -27,11 +29,20 @@ pub trait TextureStorage<ID:TextureID,IB> {
 
 pub trait MeshStorage<ID:MeshID,M> {
     fn load_mesh(&self, mesh:M) -> Result<ID, Error>;
+    ///Frees the mesh on both sides. Assets of the manifest live as long as the process and traces share
+    ///their meshes, so nothing deletes meshes yet. It stays for assets, that are unloaded at run time
     fn delete_mesh(&self, mesh_id:ID) -> Result<(), Error>;
 }
 
-pub trait LodStorage<ID:LodID,V> {
-    fn load_lod(&self, vertex_buffer:Vec<V>) -> Result<ID, Error>;
+pub trait LodStorage<ID:LodID,V:VertexKey> {
+    ///Loads a triangle list, equal vertices are merged into an index buffer
+    fn load_lod(&self, vertex_buffer:Vec<V>) -> Result<ID, Error> {
+        let (vertices, indices)=index_vertices(&vertex_buffer);
+        self.load_indexed_lod(vertices, indices)
+    }
+
+    fn load_indexed_lod(&self, vertices:Vec<V>, indices:Vec<u32>) -> Result<ID, Error>;
+    ///Frees the lod on both sides, like MeshStorage::delete_mesh
     fn delete_lod(&self, lod_id:ID) -> Result<(), Error>;
 }
 
@@ -44,6 +55,8 @@ struct InnerStorage {
     render_sender:RenderSender,
 
     textures_rgba:InnerTextureStorage<RgbaTextureID>,
+    ///Textures loaded from files, keyed by the canonical path
+    texture_paths:HashMap<String,RgbaTextureID>,
     //textures_rgbaa:InnerTextureStorage<RgbaTextureID>,
 
     object_meshes:InnerMeshStorage<ObjectMeshID>,
@@ -60,6 +73,7 @@ impl InnerStorage {
             render_sender,
 
             textures_rgba:InnerTextureStorage::new(),
+            texture_paths:HashMap::new(),
 
             object_meshes:InnerMeshStorage::new(),
             terrain_meshes:InnerMeshStorage::new(),
@@ -79,69 +93,100 @@ impl Storage {
             inner:Arc::new(Mutex::new(inner))
         }
     }
+
+    pub fn get_texture_by_path(&self, path:&str) -> Result<Option<RgbaTextureID>, Error> {
+        mutex_lock!(&self.inner => storage, Error);
+
+        ok!(storage.texture_paths.get(path).cloned())
+    }
+
+    pub fn set_texture_path(&self, path:String, texture_id:RgbaTextureID) -> Result<(), Error> {
+        mutex_lock!(&self.inner => storage, Error);
+
+        storage.texture_paths.insert(path.clone(), texture_id);
+
+        try_send!(storage.render_sender, RenderCommand::WatchTexture(path, texture_id));
+
+        ok!()
+    }
 }
 
 struct InnerTextureStorage<ID:TextureID> {
-    pool:Pool<(),()>,
+    handles:HandleAllocator,
     _phantom_data:PhantomData<ID>
 }
 
 impl<ID:TextureID> InnerTextureStorage<ID> {
     fn new() -> Self {
         InnerTextureStorage {
-            pool:Pool::new(),
+            handles:HandleAllocator::new(),
             _phantom_data:PhantomData
         }
     }
 
     fn insert(&mut self) -> ID {
-        let id=self.pool.insert(());
+        let id=self.handles.insert();
         let texture_id=ID::new(id);
 
         texture_id
     }
+
+    ///Releases the handle, its slot will be reused by the next insert with the next generation
+    fn remove(&mut self, texture_id:ID) -> bool {
+        self.handles.remove(texture_id.get_id())
+    }
 }
 
 struct InnerMeshStorage<ID:MeshID> {
-    pool:Pool<(),()>,
+    handles:HandleAllocator,
     _phantom_data:PhantomData<ID>
 }
 
 impl<ID:MeshID> InnerMeshStorage<ID> {
     fn new() -> Self {
         InnerMeshStorage {
-            pool:Pool::new(),
+            handles:HandleAllocator::new(),
             _phantom_data:PhantomData
         }
     }
 
     fn insert(&mut self) -> ID {
-        let id=self.pool.insert(());
+        let id=self.handles.insert();
         let mesh_id=ID::new(id);
 
         mesh_id
     }
+
+    ///Releases the handle, its slot will be reused by the next insert with the next generation
+    fn remove(&mut self, mesh_id:ID) -> bool {
+        self.handles.remove(mesh_id.get_id())
+    }
 }
 
 struct InnerLodStorage<ID:LodID> {
-    pool:Pool<(),()>,
+    handles:HandleAllocator,
     _phantom_data:PhantomData<ID>
 }
 
 impl<ID:LodID> InnerLodStorage<ID> {
     fn new() -> Self {
         InnerLodStorage {
-            pool:Pool::new(),
+            handles:HandleAllocator::new(),
             _phantom_data:PhantomData
         }
     }
 
     fn insert(&mut self) -> ID {
-        let id=self.pool.insert(());
+        let id=self.handles.insert();
         let lod_id=ID::new(id);
 
         lod_id
     }
+
+    ///Releases the handle, its slot will be reused by the next insert with the next generation
+    fn remove(&mut self, lod_id:ID) -> bool {
+        self.handles.remove(lod_id.get_id())
+    }
 }
 
 impl TextureStorage<RgbaTextureID, RgbaImage> for Storage {
@@ -158,6 +203,14 @@ impl TextureStorage<RgbaTextureID, RgbaImage> for Storage {
     fn delete_texture(&self, texture_id:RgbaTextureID) -> Result<(), Error> {
         mutex_lock!(&self.inner => storage, Error);
 
+        if !storage.textures_rgba.remove(texture_id) {
+            return err!(Error::NoTexture, texture_id.get_id());
+        }
+
+        storage.texture_paths.retain(|_,cached_id| *cached_id!=texture_id);
+
+        try_send!(storage.render_sender, DeleteTexture::RGBA(texture_id).into());
+
         ok!()
     }
 }
@@ -176,6 +229,12 @@ impl MeshStorage<ObjectMeshID, ObjectMesh> for Storage {
     fn delete_mesh(&self, mesh_id:ObjectMeshID) -> Result<(), Error> {
         mutex_lock!(&self.inner => storage, Error);
 
+        if !storage.object_meshes.remove(mesh_id) {
+            return err!(Error::NoMesh, mesh_id.get_id());
+        }
+
+        try_send!(storage.render_sender, DeleteMesh::Object(mesh_id).into());
+
         ok!()
     }
 }
@@ -194,6 +253,12 @@ impl MeshStorage<TerrainMeshID, TerrainMesh> for Storage {
     fn delete_mesh(&self, mesh_id:TerrainMeshID) -> Result<(), Error> {
         mutex_lock!(&self.inner => storage, Error);
 
+        if !storage.terrain_meshes.remove(mesh_id) {
+            return err!(Error::NoMesh, mesh_id.get_id());
+        }
+
+        try_send!(storage.render_sender, DeleteMesh::Terrain(mesh_id).into());
+
         ok!()
     }
 }
@@ -212,17 +277,23 @@ impl MeshStorage<TraceMeshID, TraceMesh> for Storage {
     fn delete_mesh(&self, mesh_id:TraceMeshID) -> Result<(), Error> {
         mutex_lock!(&self.inner => storage, Error);
 
+        if !storage.trace_meshes.remove(mesh_id) {
+            return err!(Error::NoMesh, mesh_id.get_id());
+        }
+
+        try_send!(storage.render_sender, DeleteMesh::Trace(mesh_id).into());
+
         ok!()
     }
 }
 
 impl LodStorage<ObjectLodID, ObjectVertex> for Storage {
-    fn load_lod(&self, vertex_buffer:Vec<ObjectVertex>) -> Result<ObjectLodID, Error> {
+    fn load_indexed_lod(&self, vertices:Vec<ObjectVertex>, indices:Vec<u32>) -> Result<ObjectLodID, Error> {
         mutex_lock!(&self.inner => storage, Error);
 
         let lod_id=storage.object_lods.insert();
 
-        try_send!(storage.render_sender, LoadLod::Object(vertex_buffer, lod_id.clone()).into());
+        try_send!(storage.render_sender, LoadLod::Object(vertices, indices, lod_id.clone()).into());
 
         ok!(lod_id)
     }
@@ -230,17 +301,23 @@ impl LodStorage<ObjectLodID, ObjectVertex> for Storage {
     fn delete_lod(&self, lod_id:ObjectLodID) -> Result<(), Error> {
         mutex_lock!(&self.inner => storage, Error);
 
+        if !storage.object_lods.remove(lod_id) {
+            return err!(Error::NoLod, lod_id.get_id());
+        }
+
+        try_send!(storage.render_sender, DeleteLod::Object(lod_id).into());
+
         ok!()
     }
 }
 
 impl LodStorage<TraceLodID, TraceVertex> for Storage {
-    fn load_lod(&self, vertex_buffer:Vec<TraceVertex>) -> Result<TraceLodID, Error> {
+    fn load_indexed_lod(&self, vertices:Vec<TraceVertex>, indices:Vec<u32>) -> Result<TraceLodID, Error> {
         mutex_lock!(&self.inner => storage, Error);
 
         let lod_id=storage.trace_lods.insert();
 
-        try_send!(storage.render_sender, LoadLod::Trace(vertex_buffer, lod_id.clone()).into());
+        try_send!(storage.render_sender, LoadLod::Trace(vertices, indices, lod_id.clone()).into());
 
         ok!(lod_id)
     }
@@ -248,6 +325,12 @@ impl LodStorage<TraceLodID, TraceVertex> for Storage {
     fn delete_lod(&self, lod_id:TraceLodID) -> Result<(), Error> {
         mutex_lock!(&self.inner => storage, Error);
 
+        if !storage.trace_lods.remove(lod_id) {
+            return err!(Error::NoLod, lod_id.get_id());
+        }
+
+        try_send!(storage.render_sender, DeleteLod::Trace(lod_id).into());
+
         ok!()
     }
 }
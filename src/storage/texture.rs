@@ -2,19 +2,22 @@ use std;
 use nes::{ErrorInfo,ErrorInfoTrait};
 use image;
 
-use object_pool::growable::ID;
+use types::RgbaImage;
+
+use super::Handle;
 
 use std::fs::File;
-use std::io::{Read,Cursor};
+use std::io::Read;
+use std::path::Path;
 
 use super::Error;
 use super::Storage;
 use super::TextureStorage;
 
 pub trait TextureID {
-    fn new(id:ID) -> Self;
+    fn new(id:Handle) -> Self;
     fn zeroed() -> Self;
-    fn get_id(&self) -> ID;
+    fn get_id(&self) -> Handle;
 }
 
 /*
@@ -22,8 +25,8 @@ pub trait TextureID {
 pub struct RgbTextureID(ID);
 
 impl TextureID for RgbTextureID {
-    fn new(id:ID) -> Self {RgbTextureID(id)}
-    fn get_id(&self) -> ID {self.0}
+    fn new(id:Handle) -> Self {RgbTextureID(id)}
+    fn get_id(&self) -> Handle {self.0}
 }
 
 */
@@ -31,32 +34,75 @@ impl TextureID for RgbTextureID {
 pub struct RgbaTexture {}
 
 impl RgbaTexture {
+    ///Loads a PNG, JPEG, BMP or TGA image. A file, that has been loaded already, is not loaded again
+    ///and its texture is returned
     pub fn load(file_name:&str, storage:&Storage) -> Result<RgbaTextureID,Error> {
+        //The same file may be referenced by different relative paths
+        let path=match std::fs::canonicalize(file_name) {
+            Ok(path) => path.to_string_lossy().into_owned(),
+            Err(_) => return err!(Error::OpenImageFileError, file_name.to_string()),
+        };
+
+        if let Some(texture_id)=storage.get_texture_by_path(&path)? {
+            return ok!(texture_id);
+        }
+
+        let image_buffer=Self::decode(file_name)?;
+        let texture_id=storage.load_texture(image_buffer)?;
+
+        storage.set_texture_path(path, texture_id)?;
+
+        ok!(texture_id)
+    }
+
+    pub fn decode(file_name:&str) -> Result<RgbaImage,Error> {
         let mut file = match File::open(file_name) {
             Ok(file) => file,
-            Err(err) => return err!(Error::OpenImageFileError, file_name.to_string()),
+            Err(_) => return err!(Error::OpenImageFileError, file_name.to_string()),
         };
 
         let mut buf=Vec::with_capacity(1024*16);
 
-        let cursor=match file.read_to_end(&mut buf) {
-            Ok(_) => Cursor::new(buf),
-            Err(err) => return err!(Error::ReadImageFileError, file_name.to_string()),
-        };
+        match file.read_to_end(&mut buf) {
+            Ok(_) => {},
+            Err(_) => return err!(Error::ReadImageFileError, file_name.to_string()),
+        }
 
-        let image_buffer = image::load(cursor, image::PNG).unwrap().to_rgba();
+        let format=detect_format(file_name, &buf)?;
+
+        match image::load_from_memory_with_format(&buf[..], format) {
+            Ok(image) => ok!(image.to_rgba()),
+            Err(error) => err!(Error::DecodeImageError, file_name.to_string(), format!("{}", error)),
+        }
+    }
+}
 
-        storage.load_texture(image_buffer)
+///Format is detected by the signature. TGA has no signature, so it is recognized by the extension
+fn detect_format(file_name:&str, buf:&[u8]) -> Result<image::ImageFormat,Error> {
+    match image::guess_format(buf) {
+        Ok(image::ImageFormat::PNG) => ok!(image::ImageFormat::PNG),
+        Ok(image::ImageFormat::JPEG) => ok!(image::ImageFormat::JPEG),
+        Ok(image::ImageFormat::BMP) => ok!(image::ImageFormat::BMP),
+        Ok(format) => err!(Error::UnsupportedImageFormatError, file_name.to_string(), format!("{:?}", format)),
+        Err(_) => {
+            let extension=Path::new(file_name).extension()
+                .map(|extension| extension.to_string_lossy().to_lowercase());
+
+            match extension {
+                Some(ref extension) if extension=="tga" => ok!(image::ImageFormat::TGA),
+                _ => err!(Error::UnknownImageFormatError, file_name.to_string()),
+            }
+        }
     }
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
-pub struct RgbaTextureID(ID);
+pub struct RgbaTextureID(Handle);
 
 impl TextureID for RgbaTextureID {
-    fn new(id:ID) -> Self {RgbaTextureID(id)}
-    fn zeroed() -> Self {RgbaTextureID(ID::zeroed())}
-    fn get_id(&self) -> ID {self.0}
+    fn new(id:Handle) -> Self {RgbaTextureID(id)}
+    fn zeroed() -> Self {RgbaTextureID(Handle::zeroed())}
+    fn get_id(&self) -> Handle {self.0}
 }
 
 
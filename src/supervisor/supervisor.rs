@@ -174,7 +174,6 @@ impl Supervisor {
 
             thread::sleep(Duration::new(0,20_000_000));
 
-            try_send![self.render_sender, RenderCommand::Tick];
             try_send![self.controller_sender, ControllerCommand::Tick];
         }
 